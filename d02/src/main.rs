@@ -1,3 +1,5 @@
+mod query;
+
 use std::{cmp::max, collections::HashMap};
 
 use nom::{
@@ -9,8 +11,32 @@ use nom::{
     IResult,
 };
 
+use query::Query;
+
 fn main() {
     let input = include_str!("../input.txt");
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some("query") = args.first().map(String::as_str) {
+        // cargo run -- query "any round has blue > 10"
+        let query = match Query::parse(&args[1..].join(" ")) {
+            Ok(query) => query,
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        };
+        for game in parse_games(input) {
+            match query.matches(&game) {
+                Ok(true) => println!("{}", game.id),
+                Ok(false) => {}
+                Err(e) => {
+                    eprintln!("{}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        return;
+    }
     let cube_limits = HashMap::from([(Color::Red, 12), (Color::Green, 13), (Color::Blue, 14)]);
     println!(
         "Part 1: {:?}",
@@ -30,15 +56,7 @@ impl Game {
     fn parse(input: &str) -> IResult<&str, Self> {
         let (input, id) = map_res(preceded(tag("Game "), digit1), |d: &str| d.parse())(input)?;
         let (input, rounds) = preceded(tag(": "), separated_list1(tag("; "), Round::parse))(input)?;
-        Ok((input, Game { id: id, rounds }))
-    }
-
-    fn is_possible(&self, cube_limits: &HashMap<Color, usize>) -> bool {
-        self.rounds.iter().all(|r| {
-            r.cubes
-                .iter()
-                .all(|c| c.count <= *cube_limits.get(&c.color).unwrap_or(&(0 as usize)))
-        })
+        Ok((input, Game { id, rounds }))
     }
 }
 
@@ -62,13 +80,13 @@ impl Cube {
             input,
             Cube {
                 color: Color::parse(color),
-                count: count,
+                count,
             },
         ))
     }
 }
 
-#[derive(Debug, PartialEq, Hash, Eq, Clone)]
+#[derive(Debug, PartialEq, Hash, Eq, Clone, Copy)]
 enum Color {
     Red,
     Green,
@@ -94,23 +112,29 @@ impl Round {
     }
 }
 
-fn possible_games(input: &str, cube_limits: &HashMap<Color, usize>) -> Vec<usize> {
+fn parse_games(input: &str) -> Vec<Game> {
     let (_, games) = separated_list1(line_ending, Game::parse)(input).unwrap();
     games
+}
+
+fn possible_games(input: &str, cube_limits: &HashMap<Color, usize>) -> Vec<usize> {
+    let query = Query::possible(cube_limits);
+    parse_games(input)
         .iter()
-        .filter(|g| g.is_possible(cube_limits))
+        // only comparisons, which can't overflow
+        .filter(|g| query.matches(g).unwrap())
         .map(|g| g.id)
         .collect()
 }
 
 fn fewest_cubes(input: &str) -> Vec<HashMap<Color, usize>> {
-    let (_, games) = separated_list1(line_ending, Game::parse)(input).unwrap();
+    let games = parse_games(input);
     let mut cube_limits =
         vec![HashMap::from([(Color::Red, 0), (Color::Green, 0), (Color::Blue, 0)]); games.len()];
     for (i, game) in games.iter().enumerate() {
         for round in &game.rounds {
             for cube in &round.cubes {
-                let color = cube.color.clone();
+                let color = cube.color;
                 let count = cube.count;
                 let mut limits = cube_limits[i].clone();
                 *limits.entry(color).or_insert(0) = max(count, *limits.get(&color).unwrap_or(&0));
@@ -0,0 +1,538 @@
+use std::collections::HashMap;
+
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::{alpha1, alphanumeric1, char, digit1, multispace0},
+    combinator::{all_consuming, map, map_res, not, opt, peek, value, verify},
+    multi::many0,
+    sequence::{delimited, pair, preceded, terminated, tuple},
+    IResult,
+};
+
+use crate::{Color, Game, Round};
+
+// A query is evaluated against a whole game. Quantifiers (`any round has ...`,
+// `all rounds ...`) switch into round scope, where bare colour names refer to
+// the cubes shown in that round. Everywhere else only game-level values are
+// available: `id`, `rounds` and the `max`/`min`/`sum` aggregates over rounds.
+//
+//   any round has blue > 10
+//   all rounds red <= 12 and green <= 13
+//   max(red) * max(blue) > 100
+//   (any round has red > 5) and not id < 3
+#[derive(Debug, PartialEq, Clone)]
+pub enum Query {
+    Compare(Compare, Expr, Expr),
+    And(Box<Query>, Box<Query>),
+    Or(Box<Query>, Box<Query>),
+    Not(Box<Query>),
+    Any(Box<Query>),
+    All(Box<Query>),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Expr {
+    Number(i64),
+    Cubes(Color),
+    Aggregate(Aggregate, Color),
+    Id,
+    Rounds,
+    Arithmetic(Operator, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Compare {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Operator {
+    Add,
+    Sub,
+    Mul,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Aggregate {
+    Max,
+    Min,
+    Sum,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Scope {
+    Game,
+    Round,
+}
+
+impl Query {
+    pub fn parse(input: &str) -> Result<Self, String> {
+        all_consuming(delimited(multispace0, game_query, multispace0))(input)
+            .map(|(_, query)| query)
+            .map_err(|e| match e {
+                nom::Err::Error(e) | nom::Err::Failure(e) if e.input.trim().is_empty() => {
+                    format!("invalid query {:?}: it stops too soon", input)
+                }
+                nom::Err::Error(e) | nom::Err::Failure(e) => format!(
+                    "invalid query {:?}: can't make sense of {:?} at column {}",
+                    input,
+                    e.input,
+                    input.len() - e.input.len() + 1
+                ),
+                nom::Err::Incomplete(_) => format!("invalid query {:?}", input),
+            })
+    }
+
+    // The part 1 question: every round fits inside the given bag
+    pub fn possible(cube_limits: &HashMap<Color, usize>) -> Self {
+        let within_limits = [Color::Red, Color::Green, Color::Blue]
+            .into_iter()
+            .map(|color| {
+                let limit = *cube_limits.get(&color).unwrap_or(&0);
+                Query::Compare(Compare::Le, Expr::Cubes(color), Expr::Number(limit as i64))
+            })
+            .reduce(|a, b| Query::And(Box::new(a), Box::new(b)))
+            .unwrap();
+        Query::All(Box::new(within_limits))
+    }
+
+    // fails if the arithmetic overflows for this game
+    pub fn matches(&self, game: &Game) -> Result<bool, String> {
+        self.eval(game, None)
+    }
+
+    fn eval(&self, game: &Game, round: Option<&Round>) -> Result<bool, String> {
+        Ok(match self {
+            Query::Compare(op, lhs, rhs) => {
+                let (lhs, rhs) = (lhs.eval(game, round)?, rhs.eval(game, round)?);
+                match op {
+                    Compare::Lt => lhs < rhs,
+                    Compare::Le => lhs <= rhs,
+                    Compare::Gt => lhs > rhs,
+                    Compare::Ge => lhs >= rhs,
+                    Compare::Eq => lhs == rhs,
+                    Compare::Ne => lhs != rhs,
+                }
+            }
+            Query::And(a, b) => a.eval(game, round)? && b.eval(game, round)?,
+            Query::Or(a, b) => a.eval(game, round)? || b.eval(game, round)?,
+            Query::Not(q) => !q.eval(game, round)?,
+            Query::Any(q) => {
+                for r in &game.rounds {
+                    if q.eval(game, Some(r))? {
+                        return Ok(true);
+                    }
+                }
+                false
+            }
+            Query::All(q) => {
+                for r in &game.rounds {
+                    if !q.eval(game, Some(r))? {
+                        return Ok(false);
+                    }
+                }
+                true
+            }
+        })
+    }
+}
+
+impl Expr {
+    fn eval(&self, game: &Game, round: Option<&Round>) -> Result<i64, String> {
+        Ok(match self {
+            Expr::Number(n) => *n,
+            // the parser only allows bare colours inside a quantifier
+            Expr::Cubes(color) => cubes_in_round(game, round.unwrap(), color)?,
+            Expr::Aggregate(aggregate, color) => {
+                let counts = game
+                    .rounds
+                    .iter()
+                    .map(|r| cubes_in_round(game, r, color))
+                    .collect::<Result<Vec<_>, _>>()?;
+                match aggregate {
+                    Aggregate::Max => counts.into_iter().max().unwrap_or(0),
+                    Aggregate::Min => counts.into_iter().min().unwrap_or(0),
+                    Aggregate::Sum => counts
+                        .into_iter()
+                        .try_fold(0i64, |total, n| total.checked_add(n))
+                        .ok_or_else(|| overflow(game, "sum"))?,
+                }
+            }
+            Expr::Id => game.id as i64,
+            Expr::Rounds => game.rounds.len() as i64,
+            Expr::Arithmetic(op, lhs, rhs) => {
+                let (lhs, rhs) = (lhs.eval(game, round)?, rhs.eval(game, round)?);
+                let (result, symbol) = match op {
+                    Operator::Add => (lhs.checked_add(rhs), '+'),
+                    Operator::Sub => (lhs.checked_sub(rhs), '-'),
+                    Operator::Mul => (lhs.checked_mul(rhs), '*'),
+                };
+                result.ok_or_else(|| overflow(game, &format!("{} {} {}", lhs, symbol, rhs)))?
+            }
+        })
+    }
+}
+
+fn overflow(game: &Game, what: &str) -> String {
+    format!("game {}: {} overflows", game.id, what)
+}
+
+fn cubes_in_round(game: &Game, round: &Round, color: &Color) -> Result<i64, String> {
+    round
+        .cubes
+        .iter()
+        .filter(|c| c.color == *color)
+        .try_fold(0i64, |total, c| {
+            i64::try_from(c.count)
+                .ok()
+                .and_then(|count| total.checked_add(count))
+        })
+        .ok_or_else(|| {
+            let color = format!("{:?}", color).to_lowercase();
+            overflow(game, &format!("the number of {} cubes in a round", color))
+        })
+}
+
+fn game_query(input: &str) -> IResult<&str, Query> {
+    or_query(Scope::Game, input)
+}
+
+fn round_query(input: &str) -> IResult<&str, Query> {
+    or_query(Scope::Round, input)
+}
+
+// a or b or c
+fn or_query(scope: Scope, input: &str) -> IResult<&str, Query> {
+    let (input, first) = and_query(scope, input)?;
+    let (input, rest) = many0(preceded(keyword("or"), |i| and_query(scope, i)))(input)?;
+    Ok((
+        input,
+        rest.into_iter()
+            .fold(first, |a, b| Query::Or(Box::new(a), Box::new(b))),
+    ))
+}
+
+// a and b and c
+fn and_query(scope: Scope, input: &str) -> IResult<&str, Query> {
+    let (input, first) = not_query(scope, input)?;
+    let (input, rest) = many0(preceded(keyword("and"), |i| not_query(scope, i)))(input)?;
+    Ok((
+        input,
+        rest.into_iter()
+            .fold(first, |a, b| Query::And(Box::new(a), Box::new(b))),
+    ))
+}
+
+fn not_query(scope: Scope, input: &str) -> IResult<&str, Query> {
+    alt((
+        map(preceded(keyword("not"), |i| not_query(scope, i)), |q| {
+            Query::Not(Box::new(q))
+        }),
+        |i| quantified_query(scope, i),
+        delimited(
+            terminated(char('('), multispace0),
+            |i| or_query(scope, i),
+            preceded(multispace0, char(')')),
+        ),
+        |i| comparison(scope, i),
+    ))(input)
+}
+
+// any round has <round query>
+// all rounds <round query>
+fn quantified_query(scope: Scope, input: &str) -> IResult<&str, Query> {
+    if scope == Scope::Round {
+        // quantifiers don't nest
+        return Err(nom::Err::Error(nom::error::Error::new(
+            input,
+            nom::error::ErrorKind::Verify,
+        )));
+    }
+    let (input, any) = alt((value(true, keyword("any")), value(false, keyword("all"))))(input)?;
+    let (input, _) = alt((keyword("rounds"), keyword("round")))(input)?;
+    let (input, _) = opt(keyword("has"))(input)?;
+    let (input, query) = round_query(input)?;
+    let query = Box::new(query);
+    Ok((
+        input,
+        if any {
+            Query::Any(query)
+        } else {
+            Query::All(query)
+        },
+    ))
+}
+
+// red + 1 <= max(green)
+fn comparison(scope: Scope, input: &str) -> IResult<&str, Query> {
+    let (input, (lhs, op, rhs)) = tuple((
+        |i| sum_expr(scope, i),
+        delimited(multispace0, compare_op, multispace0),
+        |i| sum_expr(scope, i),
+    ))(input)?;
+    Ok((input, Query::Compare(op, lhs, rhs)))
+}
+
+fn compare_op(input: &str) -> IResult<&str, Compare> {
+    alt((
+        value(Compare::Le, tag("<=")),
+        value(Compare::Ge, tag(">=")),
+        value(Compare::Ne, tag("!=")),
+        value(Compare::Eq, tag("==")),
+        value(Compare::Lt, tag("<")),
+        value(Compare::Gt, tag(">")),
+        value(Compare::Eq, tag("=")),
+    ))(input)
+}
+
+fn sum_expr(scope: Scope, input: &str) -> IResult<&str, Expr> {
+    let (input, first) = product_expr(scope, input)?;
+    let (input, rest) = many0(pair(
+        delimited(
+            multispace0,
+            alt((
+                value(Operator::Add, char('+')),
+                value(Operator::Sub, char('-')),
+            )),
+            multispace0,
+        ),
+        |i| product_expr(scope, i),
+    ))(input)?;
+    Ok((
+        input,
+        rest.into_iter().fold(first, |a, (op, b)| {
+            Expr::Arithmetic(op, Box::new(a), Box::new(b))
+        }),
+    ))
+}
+
+fn product_expr(scope: Scope, input: &str) -> IResult<&str, Expr> {
+    let (input, first) = atom(scope, input)?;
+    let (input, rest) = many0(preceded(
+        delimited(multispace0, char('*'), multispace0),
+        |i| atom(scope, i),
+    ))(input)?;
+    Ok((
+        input,
+        rest.into_iter().fold(first, |a, b| {
+            Expr::Arithmetic(Operator::Mul, Box::new(a), Box::new(b))
+        }),
+    ))
+}
+
+fn atom(scope: Scope, input: &str) -> IResult<&str, Expr> {
+    alt((
+        map(map_res(digit1, |d: &str| d.parse()), Expr::Number),
+        map(
+            pair(
+                aggregate,
+                delimited(
+                    tuple((multispace0, char('('), multispace0)),
+                    color,
+                    tuple((multispace0, char(')'))),
+                ),
+            ),
+            |(aggregate, color)| Expr::Aggregate(aggregate, color),
+        ),
+        value(Expr::Id, identifier("id")),
+        value(Expr::Rounds, identifier("rounds")),
+        verify(map(color, Expr::Cubes), move |_| scope == Scope::Round),
+        delimited(
+            terminated(char('('), multispace0),
+            |i| sum_expr(scope, i),
+            preceded(multispace0, char(')')),
+        ),
+    ))(input)
+}
+
+fn aggregate(input: &str) -> IResult<&str, Aggregate> {
+    alt((
+        value(Aggregate::Max, tag("max")),
+        value(Aggregate::Min, tag("min")),
+        value(Aggregate::Sum, tag("sum")),
+    ))(input)
+}
+
+fn color(input: &str) -> IResult<&str, Color> {
+    alt((
+        value(Color::Red, identifier("red")),
+        value(Color::Green, identifier("green")),
+        value(Color::Blue, identifier("blue")),
+    ))(input)
+}
+
+// matches a whole word, so `id` doesn't match the start of `ids`
+fn identifier<'a>(word: &'static str) -> impl FnMut(&'a str) -> IResult<&'a str, &'a str> {
+    verify(alpha1, move |w: &str| w == word)
+}
+
+// a whole word, e.g. `and`, which can run straight into a bracket
+fn keyword<'a>(word: &'static str) -> impl FnMut(&'a str) -> IResult<&'a str, &'a str> {
+    delimited(
+        multispace0,
+        terminated(identifier(word), not(peek(alphanumeric1))),
+        multispace0,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_games;
+
+    static EXAMPLE_1: &str = r#"Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
+Game 2: 1 blue, 2 green; 3 green, 4 blue, 1 red; 1 green, 1 blue
+Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red
+Game 4: 1 green, 3 red, 6 blue; 3 green, 6 red; 3 green, 15 blue, 14 red
+Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green"#;
+
+    fn matching_ids(query: &str) -> Vec<usize> {
+        let query = Query::parse(query).unwrap();
+        parse_games(EXAMPLE_1)
+            .iter()
+            .filter(|g| query.matches(g).unwrap())
+            .map(|g| g.id)
+            .collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            Query::parse("all rounds red <= 12 and green <= 13").unwrap(),
+            Query::All(Box::new(Query::And(
+                Box::new(Query::Compare(
+                    Compare::Le,
+                    Expr::Cubes(Color::Red),
+                    Expr::Number(12)
+                )),
+                Box::new(Query::Compare(
+                    Compare::Le,
+                    Expr::Cubes(Color::Green),
+                    Expr::Number(13)
+                )),
+            )))
+        );
+        assert_eq!(
+            Query::parse("max(red) * max(blue) > 100").unwrap(),
+            Query::Compare(
+                Compare::Gt,
+                Expr::Arithmetic(
+                    Operator::Mul,
+                    Box::new(Expr::Aggregate(Aggregate::Max, Color::Red)),
+                    Box::new(Expr::Aggregate(Aggregate::Max, Color::Blue)),
+                ),
+                Expr::Number(100)
+            )
+        );
+    }
+
+    #[test]
+    fn test_keywords_before_brackets() {
+        assert_eq!(
+            Query::parse("not(id = 3)").unwrap(),
+            Query::parse("not (id = 3)").unwrap()
+        );
+        assert_eq!(
+            Query::parse("any round has(blue > 10)").unwrap(),
+            Query::parse("any round has blue > 10").unwrap()
+        );
+        assert_eq!(matching_ids("(id = 1)or(id = 2)"), [1, 2]);
+        // but a keyword still has to be a whole word
+        assert!(Query::parse("notid = 3").is_err());
+        assert!(Query::parse("any roundhas blue > 10").is_err());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        // bare colours need a round to refer to
+        assert!(Query::parse("red > 1").is_err());
+        assert!(Query::parse("any round has any round has red > 1").is_err());
+        assert!(Query::parse("max(purple) > 1").is_err());
+        assert!(Query::parse("any round has blue > 10 trailing").is_err());
+        assert_eq!(
+            Query::parse("id > 1 trailing"),
+            Err(
+                "invalid query \"id > 1 trailing\": can't make sense of \"trailing\" at column 8"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            Query::parse("id >"),
+            Err("invalid query \"id >\": it stops too soon".to_string())
+        );
+    }
+
+    #[test]
+    fn test_queries() {
+        assert_eq!(matching_ids("any round has blue > 10"), [4]);
+        assert_eq!(
+            matching_ids("all rounds red <= 12 and green <= 13 and blue <= 14"),
+            [1, 2, 5]
+        );
+        assert_eq!(matching_ids("max(red) * max(blue) > 100"), [3, 4]);
+        assert_eq!(
+            matching_ids("(any round has red > 5) and not id = 3"),
+            [4, 5]
+        );
+        assert_eq!(matching_ids("rounds = 2 or sum(green) - 1 >= 20"), [3, 5]);
+    }
+
+    #[test]
+    fn test_overflow() {
+        let game = &parse_games(EXAMPLE_1)[0];
+        let query = Query::parse("max(red) * 9223372036854775807 > 0").unwrap();
+        assert_eq!(
+            query.matches(game),
+            Err("game 1: 4 * 9223372036854775807 overflows".to_string())
+        );
+        let query = Query::parse("0 - 9223372036854775807 - id - 1 < 0").unwrap();
+        assert!(query.matches(game).is_err());
+        // short-circuiting never gets as far as the overflow
+        let query = Query::parse("id = 2 and max(red) * 9223372036854775807 > 0").unwrap();
+        assert_eq!(query.matches(game), Ok(false));
+    }
+
+    #[test]
+    fn test_huge_counts() {
+        let max = i64::MAX;
+        let games = parse_games(&format!("Game 1: {} red, 1 red; 1 blue", max));
+        let query = Query::parse("any round has red > 0").unwrap();
+        assert_eq!(
+            query.matches(&games[0]),
+            Err("game 1: the number of red cubes in a round overflows".to_string())
+        );
+        let games = parse_games(&format!("Game 1: {} red; 1 red", max as u64 + 1));
+        assert!(Query::parse("max(red) > 0")
+            .unwrap()
+            .matches(&games[0])
+            .is_err());
+        let games = parse_games(&format!("Game 1: {} red; 1 red", max));
+        assert!(Query::parse("sum(red) > 0")
+            .unwrap()
+            .matches(&games[0])
+            .is_err());
+        assert_eq!(
+            Query::parse("max(red) > 0").unwrap().matches(&games[0]),
+            Ok(true)
+        );
+    }
+
+    #[test]
+    fn test_possible_query() {
+        let query = Query::possible(&HashMap::from([
+            (Color::Red, 12),
+            (Color::Green, 13),
+            (Color::Blue, 14),
+        ]));
+        assert_eq!(
+            query,
+            Query::parse("all rounds red <= 12 and green <= 13 and blue <= 14").unwrap()
+        );
+    }
+}
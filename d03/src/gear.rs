@@ -0,0 +1,233 @@
+use std::collections::HashSet;
+
+use crate::schematic::Schematic;
+
+// How many adjacent numbers a symbol needs to count as a gear
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Arity {
+    Exactly(usize),
+    AtLeast(usize),
+    Between(usize, usize),
+}
+
+impl Arity {
+    // 2 | 2.. | 2..=4 | 2..5
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let number = |s: &str| {
+            s.parse::<usize>()
+                .map_err(|e| format!("invalid arity {:?}: {}", input, e))
+        };
+        match input.split_once("..") {
+            None => Ok(Arity::Exactly(number(input)?)),
+            Some((min, "")) => Ok(Arity::AtLeast(number(min)?)),
+            Some((min, max)) => {
+                let min = number(min)?;
+                let max = match max.strip_prefix('=') {
+                    Some(max) => Some(number(max)?),
+                    None => number(max)?.checked_sub(1),
+                };
+                match max {
+                    Some(max) if min <= max => Ok(Arity::Between(min, max)),
+                    _ => Err(format!("invalid arity {:?}: empty range", input)),
+                }
+            }
+        }
+    }
+
+    fn accepts(&self, count: usize) -> bool {
+        match *self {
+            Arity::Exactly(n) => count == n,
+            Arity::AtLeast(n) => count >= n,
+            Arity::Between(min, max) => (min..=max).contains(&count),
+        }
+    }
+}
+
+// How a gear's adjacent numbers are folded into its ratio
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Combine {
+    Product,
+    Sum,
+    Max,
+}
+
+impl Combine {
+    pub fn parse(input: &str) -> Result<Self, String> {
+        match input {
+            "product" => Ok(Combine::Product),
+            "sum" => Ok(Combine::Sum),
+            "max" => Ok(Combine::Max),
+            _ => Err(format!("unknown combine function {:?}", input)),
+        }
+    }
+
+    // None if the result is too big for a u64
+    fn apply(&self, numbers: &[u64]) -> Option<u64> {
+        match self {
+            Combine::Product => numbers.iter().try_fold(1u64, |a, &b| a.checked_mul(b)),
+            Combine::Sum => numbers.iter().try_fold(0u64, |a, &b| a.checked_add(b)),
+            Combine::Max => Some(numbers.iter().copied().max().unwrap_or(0)),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct GearRule {
+    pub symbols: HashSet<char>,
+    pub arity: Arity,
+    pub combine: Combine,
+}
+
+// A symbol that satisfied a `GearRule`, with the numbers around it in reading order
+#[derive(Debug, PartialEq, Clone)]
+pub struct Gear {
+    pub row: usize,
+    pub col: usize,
+    pub glyph: char,
+    pub numbers: Vec<u64>,
+    pub ratio: u64,
+}
+
+impl GearRule {
+    // The puzzle's rule: a `*` next to exactly two numbers, multiplied together
+    pub fn standard() -> Self {
+        GearRule {
+            symbols: HashSet::from(['*']),
+            arity: Arity::Exactly(2),
+            combine: Combine::Product,
+        }
+    }
+
+    // fails on a gear whose ratio is too big for a u64
+    pub fn gears(&self, schematic: &Schematic) -> Result<Vec<Gear>, String> {
+        schematic
+            .symbols_with_neighbours(|symbol, count| {
+                self.symbols.contains(&symbol.glyph) && self.arity.accepts(count)
+            })
+            .into_iter()
+            .map(|(symbol, numbers)| {
+                let numbers = numbers.iter().map(|n| n.value).collect::<Vec<u64>>();
+                let ratio = self.combine.apply(&numbers).ok_or_else(|| {
+                    format!(
+                        "the ratio of the gear at row {}, column {} is too big",
+                        symbol.row + 1,
+                        symbol.col + 1
+                    )
+                })?;
+                Ok(Gear {
+                    row: symbol.row,
+                    col: symbol.col,
+                    glyph: symbol.glyph,
+                    ratio,
+                    numbers,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = r#"467..114..
+...*......
+..35..633.
+......#...
+617*......
+.....+.58.
+..592.....
+......755.
+...$.*....
+.664.598.."#;
+
+    #[test]
+    fn test_arity_parse() {
+        assert_eq!(Arity::parse("2"), Ok(Arity::Exactly(2)));
+        assert_eq!(Arity::parse("1.."), Ok(Arity::AtLeast(1)));
+        assert_eq!(Arity::parse("1..=3"), Ok(Arity::Between(1, 3)));
+        assert_eq!(Arity::parse("1..3"), Ok(Arity::Between(1, 2)));
+        assert!(Arity::parse("3..1").is_err());
+        assert!(Arity::parse("0..0").is_err());
+        assert!(Arity::parse("two").is_err());
+    }
+
+    #[test]
+    fn test_standard_rule() {
+        let gears = GearRule::standard()
+            .gears(&Schematic::parse(EXAMPLE).unwrap())
+            .unwrap();
+        assert_eq!(
+            gears,
+            vec![
+                Gear {
+                    row: 1,
+                    col: 3,
+                    glyph: '*',
+                    numbers: vec![467, 35],
+                    ratio: 16345
+                },
+                Gear {
+                    row: 8,
+                    col: 5,
+                    glyph: '*',
+                    numbers: vec![755, 598],
+                    ratio: 451490
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ratio_too_big() {
+        let schematic = Schematic::parse("99999999999*99999999999").unwrap();
+        assert_eq!(
+            GearRule::standard().gears(&schematic),
+            Err("the ratio of the gear at row 1, column 12 is too big".to_string())
+        );
+        let rule = GearRule {
+            combine: Combine::Sum,
+            ..GearRule::standard()
+        };
+        assert_eq!(rule.gears(&schematic).unwrap()[0].ratio, 2 * 99999999999);
+    }
+
+    #[test]
+    fn test_variant_rules() {
+        let schematic = Schematic::parse(EXAMPLE).unwrap();
+        let rule = GearRule {
+            symbols: HashSet::from(['*', '#', '+', '$']),
+            arity: Arity::AtLeast(1),
+            combine: Combine::Sum,
+        };
+        assert_eq!(
+            rule.gears(&schematic)
+                .unwrap()
+                .iter()
+                .map(|g| (g.glyph, g.ratio))
+                .collect::<Vec<_>>(),
+            vec![
+                ('*', 502),
+                ('#', 633),
+                ('*', 617),
+                ('+', 592),
+                ('$', 664),
+                ('*', 1353)
+            ]
+        );
+
+        let rule = GearRule {
+            symbols: HashSet::from(['*']),
+            arity: Arity::Between(1, 1),
+            combine: Combine::Max,
+        };
+        assert_eq!(
+            rule.gears(&schematic)
+                .unwrap()
+                .iter()
+                .map(|g| ((g.row, g.col), g.ratio))
+                .collect::<Vec<_>>(),
+            vec![((4, 3), 617)]
+        );
+    }
+}
@@ -0,0 +1,175 @@
+use std::collections::{HashMap, HashSet};
+
+use image::{ImageBuffer, Rgb, RgbImage};
+
+use crate::{
+    gear::{Gear, GearRule},
+    schematic::Schematic,
+};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Class {
+    Empty,
+    PartNumber,
+    IgnoredNumber,
+    Gear,
+    Symbol,
+}
+
+impl Class {
+    fn ansi(&self) -> &'static str {
+        match self {
+            Class::Empty => "\x1b[0;2m",
+            Class::PartNumber => "\x1b[0;32m",
+            Class::IgnoredNumber => "\x1b[0;31m",
+            Class::Gear => "\x1b[0;1;33m",
+            Class::Symbol => "\x1b[0;36m",
+        }
+    }
+
+    fn rgb(&self) -> Rgb<u8> {
+        match self {
+            Class::Empty => Rgb([32, 32, 32]),
+            Class::PartNumber => Rgb([0, 200, 0]),
+            Class::IgnoredNumber => Rgb([220, 0, 0]),
+            Class::Gear => Rgb([255, 210, 0]),
+            Class::Symbol => Rgb([0, 180, 220]),
+        }
+    }
+}
+
+// The schematic with every cell classified the way `part_numbers` and the
+// gear rule see it
+pub struct Annotation {
+    pub cells: Vec<Vec<(char, Class)>>,
+    pub gears: Vec<Gear>,
+}
+
+impl Annotation {
+    pub fn new(input: &str, rule: &GearRule) -> Result<Self, String> {
+        let schematic = Schematic::parse(input)?;
+        let gears = rule.gears(&schematic)?;
+
+        let parts = schematic
+            .part_numbers(|_| true)
+            .iter()
+            .map(|n| (n.row, n.start))
+            .collect::<HashSet<_>>();
+        let mut classes = HashMap::new();
+        for number in &schematic.numbers {
+            let class = if parts.contains(&(number.row, number.start)) {
+                Class::PartNumber
+            } else {
+                Class::IgnoredNumber
+            };
+            for col in number.start..=number.end {
+                classes.insert((number.row, col), class);
+            }
+        }
+        for symbol in &schematic.symbols {
+            classes.insert((symbol.row, symbol.col), Class::Symbol);
+        }
+        for gear in &gears {
+            classes.insert((gear.row, gear.col), Class::Gear);
+        }
+
+        let cells = input
+            .lines()
+            .enumerate()
+            .map(|(row, line)| {
+                line.chars()
+                    .enumerate()
+                    .map(|(col, c)| (c, *classes.get(&(row, col)).unwrap_or(&Class::Empty)))
+                    .collect()
+            })
+            .collect();
+        Ok(Annotation { cells, gears })
+    }
+
+    // The coloured schematic followed by one line per gear with its ratio
+    pub fn to_ansi(&self) -> String {
+        let mut out = String::new();
+        for row in &self.cells {
+            let mut previous = None;
+            for (c, class) in row {
+                // only switch colour at the start of a run
+                if previous != Some(class) {
+                    out.push_str(class.ansi());
+                    previous = Some(class);
+                }
+                out.push(*c);
+            }
+            out.push_str("\x1b[0m\n");
+        }
+        for gear in &self.gears {
+            out.push_str(&format!(
+                "{}{}\x1b[0m at ({}, {}): {:?} -> {}\n",
+                Class::Gear.ansi(),
+                gear.glyph,
+                gear.row,
+                gear.col,
+                gear.numbers,
+                gear.ratio
+            ));
+        }
+        out
+    }
+
+    // One `scale`x`scale` block per cell, coloured by class
+    pub fn to_image(&self, scale: u32) -> RgbImage {
+        let height = self.cells.len() as u32;
+        let width = self.cells.iter().map(|r| r.len()).max().unwrap_or(0) as u32;
+        ImageBuffer::from_fn(width * scale, height * scale, |x, y| {
+            self.cells[(y / scale) as usize]
+                .get((x / scale) as usize)
+                .map_or(Class::Empty, |(_, class)| *class)
+                .rgb()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classes() {
+        let annotation = Annotation::new("12.*4\n.....\n7#..9", &GearRule::standard()).unwrap();
+        assert_eq!(
+            annotation
+                .cells
+                .iter()
+                .map(|row| row.iter().map(|(_, class)| *class).collect::<Vec<_>>())
+                .collect::<Vec<_>>(),
+            vec![
+                vec![
+                    Class::IgnoredNumber,
+                    Class::IgnoredNumber,
+                    Class::Empty,
+                    Class::Symbol,
+                    Class::PartNumber
+                ],
+                vec![Class::Empty; 5],
+                vec![
+                    Class::PartNumber,
+                    Class::Symbol,
+                    Class::Empty,
+                    Class::Empty,
+                    Class::IgnoredNumber
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_gears_in_output() {
+        let annotation = Annotation::new("1*2", &GearRule::standard()).unwrap();
+        assert_eq!(
+            annotation.to_ansi(),
+            "\x1b[0;32m1\x1b[0;1;33m*\x1b[0;32m2\x1b[0m\n\x1b[0;1;33m*\x1b[0m at (0, 1): [1, 2] -> 2\n"
+        );
+        let image = annotation.to_image(2);
+        assert_eq!(image.dimensions(), (6, 2));
+        assert_eq!(*image.get_pixel(3, 1), Class::Gear.rgb());
+    }
+}
@@ -0,0 +1,234 @@
+use std::collections::HashMap;
+
+// A number in the schematic, spanning columns start..=end of a single row
+#[derive(Debug, PartialEq, Clone)]
+pub struct Number {
+    pub row: usize,
+    pub start: usize,
+    pub end: usize,
+    pub value: u64,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Symbol {
+    pub row: usize,
+    pub col: usize,
+    pub glyph: char,
+}
+
+// Every number and symbol in the schematic, in reading order, plus the
+// adjacency between them in both directions (by index into `numbers` and
+// `symbols`). A number is adjacent to a symbol if the symbol sits in the
+// rectangle one cell around the number's span, diagonals included.
+#[derive(Debug)]
+pub struct Schematic {
+    pub numbers: Vec<Number>,
+    pub symbols: Vec<Symbol>,
+    number_neighbours: Vec<Vec<usize>>,
+    symbol_neighbours: Vec<Vec<usize>>,
+}
+
+impl Schematic {
+    // fails on a number too big for a u64
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let mut numbers = vec![];
+        let mut symbols = vec![];
+        for (row, line) in input.lines().enumerate() {
+            let mut current: Option<Number> = None;
+            for (col, c) in line.chars().enumerate() {
+                if let Some(digit) = c.to_digit(10) {
+                    let number = current.get_or_insert(Number {
+                        row,
+                        start: col,
+                        end: col,
+                        value: 0,
+                    });
+                    number.end = col;
+                    number.value = number
+                        .value
+                        .checked_mul(10)
+                        .and_then(|v| v.checked_add(digit as u64))
+                        .ok_or_else(|| {
+                            format!(
+                                "the number at row {}, column {} is too big",
+                                row + 1,
+                                number.start + 1
+                            )
+                        })?;
+                    continue;
+                }
+                numbers.extend(current.take());
+                if c != '.' {
+                    symbols.push(Symbol { row, col, glyph: c });
+                }
+            }
+            // numbers can run up to the end of the row
+            numbers.extend(current);
+        }
+
+        let symbol_at: HashMap<(usize, usize), usize> = symbols
+            .iter()
+            .enumerate()
+            .map(|(i, s)| ((s.row, s.col), i))
+            .collect();
+        let mut number_neighbours = vec![vec![]; numbers.len()];
+        let mut symbol_neighbours = vec![vec![]; symbols.len()];
+        for (n, number) in numbers.iter().enumerate() {
+            for row in number.row.saturating_sub(1)..=number.row + 1 {
+                for col in number.start.saturating_sub(1)..=number.end + 1 {
+                    if let Some(&s) = symbol_at.get(&(row, col)) {
+                        number_neighbours[n].push(s);
+                        symbol_neighbours[s].push(n);
+                    }
+                }
+            }
+        }
+        // the scan above visits numbers in reading order, so each symbol's
+        // neighbours are already sorted; a number's symbols need sorting
+        number_neighbours.iter_mut().for_each(|s| s.sort());
+
+        Ok(Schematic {
+            numbers,
+            symbols,
+            number_neighbours,
+            symbol_neighbours,
+        })
+    }
+
+    // symbols adjacent to the number at `number` in `self.numbers`
+    pub fn symbols_around(&self, number: usize) -> impl Iterator<Item = &Symbol> {
+        self.number_neighbours[number]
+            .iter()
+            .map(|&s| &self.symbols[s])
+    }
+
+    // numbers adjacent to the symbol at `symbol` in `self.symbols`
+    pub fn numbers_around(&self, symbol: usize) -> impl Iterator<Item = &Number> {
+        self.symbol_neighbours[symbol]
+            .iter()
+            .map(|&n| &self.numbers[n])
+    }
+
+    // numbers adjacent to at least one symbol accepted by `is_symbol`
+    pub fn part_numbers<F>(&self, is_symbol: F) -> Vec<&Number>
+    where
+        F: Fn(char) -> bool,
+    {
+        self.numbers
+            .iter()
+            .enumerate()
+            .filter(|(n, _)| self.symbols_around(*n).any(|s| is_symbol(s.glyph)))
+            .map(|(_, number)| number)
+            .collect()
+    }
+
+    // symbols accepted by `accept`, given the symbol and how many numbers are
+    // adjacent to it, e.g. `|s, n| s.glyph == '*' && n == 2`
+    pub fn symbols_with_neighbours<F>(&self, accept: F) -> Vec<(&Symbol, Vec<&Number>)>
+    where
+        F: Fn(&Symbol, usize) -> bool,
+    {
+        self.symbols
+            .iter()
+            .enumerate()
+            .filter(|(s, symbol)| accept(symbol, self.symbol_neighbours[*s].len()))
+            .map(|(s, symbol)| (symbol, self.numbers_around(s).collect()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_spans() {
+        let schematic = Schematic::parse("12.*\n..4567\n#...").unwrap();
+        assert_eq!(
+            schematic.numbers,
+            vec![
+                Number {
+                    row: 0,
+                    start: 0,
+                    end: 1,
+                    value: 12
+                },
+                Number {
+                    row: 1,
+                    start: 2,
+                    end: 5,
+                    value: 4567
+                },
+            ]
+        );
+        assert_eq!(
+            schematic.symbols,
+            vec![
+                Symbol {
+                    row: 0,
+                    col: 3,
+                    glyph: '*'
+                },
+                Symbol {
+                    row: 2,
+                    col: 0,
+                    glyph: '#'
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_adjacency() {
+        let schematic = Schematic::parse("12.*\n..4567\n#...").unwrap();
+        assert_eq!(schematic.number_neighbours, vec![vec![], vec![0]]);
+        assert_eq!(schematic.symbol_neighbours, vec![vec![1], vec![]]);
+    }
+
+    #[test]
+    fn test_wide_numbers() {
+        // both numbers are wider than the old 3x7 gear window
+        let schematic = Schematic::parse("123456*7654321\n..............").unwrap();
+        assert_eq!(
+            schematic
+                .symbols_with_neighbours(|s, n| s.glyph == '*' && n == 2)
+                .iter()
+                .map(|(_, numbers)| numbers.iter().map(|n| n.value).collect::<Vec<_>>())
+                .collect::<Vec<_>>(),
+            vec![vec![123456, 7654321]]
+        );
+    }
+
+    #[test]
+    fn test_too_big() {
+        // u64::MAX has 20 digits
+        let max = Schematic::parse("18446744073709551615*").unwrap();
+        assert_eq!(max.numbers[0].value, u64::MAX);
+        assert_eq!(
+            Schematic::parse("..\n.18446744073709551616*").map(|s| s.numbers.len()),
+            Err("the number at row 2, column 2 is too big".to_string())
+        );
+        assert!(Schematic::parse("1000000000000000000000").is_err());
+    }
+
+    #[test]
+    fn test_symbols_near_left_edge() {
+        let schematic = Schematic::parse("1*\n.2\n*3").unwrap();
+        assert_eq!(
+            schematic
+                .symbols_with_neighbours(|s, n| s.glyph == '*' && n == 2)
+                .iter()
+                .map(|(s, _)| (s.row, s.col))
+                .collect::<Vec<_>>(),
+            vec![(0, 1), (2, 0)]
+        );
+        assert_eq!(
+            schematic
+                .part_numbers(|_| true)
+                .iter()
+                .map(|n| n.value)
+                .collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+    }
+}
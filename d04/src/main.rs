@@ -1,3 +1,7 @@
+mod scoring;
+
+use std::collections::HashSet;
+
 use nom::{
     bytes::complete::tag,
     character::complete::{digit1, space0, space1},
@@ -7,14 +11,65 @@ use nom::{
     IResult,
 };
 
+use scoring::{Doubling, ScoringRule};
+
 fn main() {
     let input = include_str!("../input.txt");
-    println!("Part 1: {}", scratchcard_points(input));
-    println!("Part 1: {}", part2(input));
+    // cargo run -- [doubling|linear|fibonacci|wrap:N] [cascade] [clamp|error]
+    let mut rule: Box<dyn ScoringRule> = Box::new(Doubling);
+    let mut overflow = Overflow::Clamp;
+    let mut trace = false;
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "cascade" => trace = true,
+            "clamp" => overflow = Overflow::Clamp,
+            "error" => overflow = Overflow::Error,
+            name => match scoring::parse(name) {
+                Ok(r) => rule = r,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    std::process::exit(1);
+                }
+            },
+        }
+    }
+
+    if trace {
+        let (_, cards) = parse_scratchcards(input).unwrap();
+        match cascade(&cards, rule.as_ref(), overflow) {
+            Ok(cascade) => {
+                for card in &cascade.cards {
+                    println!(
+                        "Card {}: {} matches, {} instances, spawned {:?}, clamped {}",
+                        card.id, card.matches, card.instances, card.spawned, card.clamped
+                    );
+                }
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+    match scratchcard_points(input, rule.as_ref()) {
+        Ok(points) => println!("Part 1: {}", points),
+        Err(e) => {
+            eprintln!("Part 1: {}", e);
+            std::process::exit(1);
+        }
+    }
+    match part2(input, rule.as_ref(), overflow) {
+        Ok(total) => println!("Part 2: {}", total),
+        Err(e) => {
+            eprintln!("Part 2: {}", e);
+            std::process::exit(1);
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Clone)]
 struct Scratchcard {
+    id: u32,
     card_numbers: Vec<u32>,
 }
 
@@ -22,7 +77,7 @@ impl Scratchcard {
     fn parse(input: &str) -> IResult<&str, Scratchcard> {
         let (input, _) = tag("Card")(input)?;
         let (input, _) = space1(input)?;
-        let (input, _) = digit1(input)?;
+        let (input, id) = map_res(digit1, |s: &str| s.parse::<u32>())(input)?;
         let (input, _) = tag(": ")(input)?;
         let (input, numbers) = separated_list1(
             space1,
@@ -31,28 +86,20 @@ impl Scratchcard {
         Ok((
             input,
             Scratchcard {
+                id,
                 card_numbers: numbers,
             },
         ))
     }
 }
 
-fn scratchcard_points(input: &str) -> u32 {
+fn scratchcard_points(input: &str, rule: &dyn ScoringRule) -> Result<u64, String> {
     let (_, cards) = parse_scratchcards(input).unwrap();
-    cards.iter().fold(0, |total, (scratchcard, my_numbers)| {
-        let out = total
-            + my_numbers.iter().fold(0, |acc, my_number| {
-                if scratchcard.card_numbers.contains(my_number) {
-                    if acc == 0 {
-                        1
-                    } else {
-                        acc * 2
-                    }
-                } else {
-                    acc
-                }
-            });
-        out
+    cards.iter().try_fold(0u64, |total, (sc, my_numbers)| {
+        let matches = scratchcard_matches(sc, my_numbers);
+        rule.points(matches)
+            .and_then(|points| total.checked_add(points))
+            .ok_or_else(|| format!("card {}: too many points to count", sc.id))
     })
 }
 
@@ -73,29 +120,99 @@ fn parse_scratchcard(input: &str) -> IResult<&str, (Scratchcard, Vec<u32>)> {
 }
 
 fn scratchcard_matches(sc: &Scratchcard, my_numbers: &[u32]) -> usize {
+    let my_numbers = my_numbers.iter().collect::<HashSet<_>>();
     sc.card_numbers
         .iter()
         .filter(|n| my_numbers.contains(n))
         .count()
 }
 
-fn part2(input: &str) -> u32 {
+fn part2(input: &str, rule: &dyn ScoringRule, overflow: Overflow) -> Result<u64, String> {
     let (_, cards) = parse_scratchcards(input).unwrap();
+    cascade(&cards, rule, overflow)?.total()
+}
+
+// What to do when a rule awards copies of cards past the end of the table
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Overflow {
+    Clamp,
+    Error,
+}
+
+#[derive(Debug, PartialEq)]
+struct CardTrace {
+    id: u32,
+    matches: usize,
+    instances: u64,
+    // (card id, copies) won by all instances of this card together
+    spawned: Vec<(u32, u64)>,
+    // wins that pointed past the last card and were dropped
+    clamped: usize,
+}
+
+#[derive(Debug, PartialEq)]
+struct Cascade {
+    cards: Vec<CardTrace>,
+}
+
+impl Cascade {
+    fn total(&self) -> Result<u64, String> {
+        self.cards
+            .iter()
+            .try_fold(0u64, |total, c| total.checked_add(c.instances))
+            .ok_or_else(|| "too many cards in total to count".to_string())
+    }
+}
 
-    // card_instances is a vector of the number of times each card appears in the scratchcards
-    // we initialise it to 1s, as each card appears once to begin with
-    // then we iterate through each card, and for each number in the card, we check how many
-    // matches we have. Then we increment n_matches positions forward by the current card_instances
-    let mut card_instances = vec![1; cards.len()];
-    for i in 0..card_instances.len() {
-        let sc = &cards[i].0;
-        let my_numbers = &cards[i].1;
-        let n_matches = scratchcard_matches(sc, my_numbers);
-        for j in 1..=n_matches {
-            card_instances[i + j] += card_instances[i];
+fn cascade(
+    cards: &[(Scratchcard, Vec<u32>)],
+    rule: &dyn ScoringRule,
+    overflow: Overflow,
+) -> Result<Cascade, String> {
+    // every card starts with one instance and the cards are scratched in
+    // order, each adding its instance count to every card it wins. With the
+    // puzzle's rule wins only point forwards, so a card's count is final by
+    // the time it's scratched. Rules that wrap round can also win copies of
+    // cards already scratched; those copies are counted but not scratched again.
+    let mut instances = vec![1u64; cards.len()];
+    let mut trace = Vec::with_capacity(cards.len());
+    for (i, (sc, my_numbers)) in cards.iter().enumerate() {
+        let matches = scratchcard_matches(sc, my_numbers);
+        let won = instances[i];
+        let mut spawned = vec![];
+        let mut clamped = 0;
+        for j in rule.wins(i, matches, cards.len()) {
+            match cards.get(j) {
+                Some((target, _)) => {
+                    instances[j] = instances[j].checked_add(won).ok_or_else(|| {
+                        format!("card {} wins too many copies to count", target.id)
+                    })?;
+                    spawned.push((target.id, won));
+                }
+                None if overflow == Overflow::Clamp => clamped += 1,
+                None => {
+                    return Err(format!(
+                        "card {} wins a copy of card number {} but there are only {} cards",
+                        sc.id,
+                        j + 1,
+                        cards.len()
+                    ))
+                }
+            }
         }
+        trace.push(CardTrace {
+            id: sc.id,
+            matches,
+            instances: won,
+            spawned,
+            clamped,
+        });
     }
-    card_instances.into_iter().sum()
+    // pick up copies won after a card was scratched
+    for (card, count) in trace.iter_mut().zip(instances) {
+        card.instances = count;
+    }
+    Ok(Cascade { cards: trace })
 }
 
 #[cfg(test)]
@@ -110,7 +227,7 @@ Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11"#;
 
     #[test]
     fn test_scratchcard_points() {
-        assert_eq!(scratchcard_points(EXAMPLE1), 13);
+        assert_eq!(scratchcard_points(EXAMPLE1, &Doubling), Ok(13));
     }
 
     #[test]
@@ -122,36 +239,42 @@ Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11"#;
                 vec![
                     (
                         Scratchcard {
+                            id: 1,
                             card_numbers: vec![41, 48, 83, 86, 17]
                         },
                         vec![83, 86, 6, 31, 17, 9, 48, 53]
                     ),
                     (
                         Scratchcard {
+                            id: 2,
                             card_numbers: vec![13, 32, 20, 16, 61]
                         },
                         vec![61, 30, 68, 82, 17, 32, 24, 19]
                     ),
                     (
                         Scratchcard {
+                            id: 3,
                             card_numbers: vec![1, 21, 53, 59, 44]
                         },
                         vec![69, 82, 63, 72, 16, 21, 14, 1]
                     ),
                     (
                         Scratchcard {
+                            id: 4,
                             card_numbers: vec![41, 92, 73, 84, 69]
                         },
                         vec![59, 84, 76, 51, 58, 5, 54, 83]
                     ),
                     (
                         Scratchcard {
+                            id: 5,
                             card_numbers: vec![87, 83, 26, 28, 32]
                         },
                         vec![88, 30, 70, 12, 93, 22, 82, 36]
                     ),
                     (
                         Scratchcard {
+                            id: 6,
                             card_numbers: vec![31, 18, 13, 56, 72]
                         },
                         vec![74, 77, 10, 23, 35, 67, 36, 11]
@@ -164,12 +287,135 @@ Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11"#;
     #[test]
     fn test_scratchcard_matches() {
         let sc = Scratchcard {
+            id: 1,
             card_numbers: vec![41, 48, 83, 86, 17],
         };
         assert_eq!(scratchcard_matches(&sc, &[83, 86, 6, 31, 17, 9, 48, 53]), 4);
     }
     #[test]
     fn test_part2() {
-        assert_eq!(part2(EXAMPLE1), 30);
+        assert_eq!(part2(EXAMPLE1, &Doubling, Overflow::Clamp), Ok(30));
+    }
+
+    #[test]
+    fn test_cascade_trace() {
+        let (_, cards) = parse_scratchcards(EXAMPLE1).unwrap();
+        let cascade = cascade(&cards, &Doubling, Overflow::Error).unwrap();
+        assert_eq!(
+            cascade.cards[..3],
+            [
+                CardTrace {
+                    id: 1,
+                    matches: 4,
+                    instances: 1,
+                    spawned: vec![(2, 1), (3, 1), (4, 1), (5, 1)],
+                    clamped: 0
+                },
+                CardTrace {
+                    id: 2,
+                    matches: 2,
+                    instances: 2,
+                    spawned: vec![(3, 2), (4, 2)],
+                    clamped: 0
+                },
+                CardTrace {
+                    id: 3,
+                    matches: 2,
+                    instances: 4,
+                    spawned: vec![(4, 4), (5, 4)],
+                    clamped: 0
+                },
+            ]
+        );
+        assert_eq!(
+            cascade
+                .cards
+                .iter()
+                .map(|c| c.instances)
+                .collect::<Vec<_>>(),
+            vec![1, 2, 4, 8, 14, 1]
+        );
+        assert_eq!(cascade.total(), Ok(30));
+    }
+
+    // a card whose winning numbers are 1..=n and which has all of them
+    fn card_with_matches(id: usize, n: usize) -> String {
+        let numbers = (1..=n).map(|i| i.to_string()).collect::<Vec<_>>().join(" ");
+        format!("Card {}: {} | {}", id, numbers, numbers)
+    }
+
+    #[test]
+    fn test_too_many_points() {
+        assert_eq!(
+            scratchcard_points(&card_with_matches(1, 64), &Doubling),
+            Ok(1 << 63)
+        );
+        assert_eq!(
+            scratchcard_points(&card_with_matches(1, 65), &Doubling),
+            Err("card 1: too many points to count".to_string())
+        );
+        let two = [card_with_matches(1, 64), card_with_matches(2, 64)].join("\n");
+        assert!(scratchcard_points(&two, &Doubling).is_err());
+        assert!(scratchcard_points(&card_with_matches(1, 93), &scoring::Fibonacci).is_err());
+    }
+
+    #[test]
+    fn test_too_many_copies() {
+        // every card wins a copy of each of the next ten, so counts grow
+        // roughly like 2^n
+        let cards = (1..=40)
+            .map(|id| card_with_matches(id, 10))
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert!(part2(&cards, &Doubling, Overflow::Clamp).is_ok_and(|total| total > 1 << 32));
+        let cards = (1..=70)
+            .map(|id| card_with_matches(id, 10))
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert!(part2(&cards, &Doubling, Overflow::Clamp)
+            .is_err_and(|e| e.ends_with("wins too many copies to count")));
+    }
+
+    #[test]
+    fn test_alternative_rules() {
+        assert_eq!(
+            scratchcard_points(EXAMPLE1, &scoring::Linear),
+            Ok(4 + 2 + 2 + 1)
+        );
+        assert_eq!(
+            scratchcard_points(EXAMPLE1, &scoring::Fibonacci),
+            Ok(5 + 2 + 2 + 1)
+        );
+
+        // cards 1, 3 and 4 all wrap round to card 1 after it's been scratched
+        let (_, cards) = parse_scratchcards(EXAMPLE1).unwrap();
+        let rule = scoring::WrapAround { ahead: 3 };
+        let cascade = cascade(&cards, &rule, Overflow::Error).unwrap();
+        assert_eq!(
+            cascade.cards[0].spawned,
+            vec![(4, 1), (5, 1), (6, 1), (1, 1)]
+        );
+        assert_eq!(cascade.cards[3].spawned, vec![(1, 2)]);
+        assert_eq!(
+            cascade
+                .cards
+                .iter()
+                .map(|c| c.instances)
+                .collect::<Vec<_>>(),
+            vec![5, 1, 1, 2, 3, 4]
+        );
+    }
+
+    #[test]
+    fn test_cascade_overflow() {
+        let (_, cards) = parse_scratchcards(
+            r#"Card 1: 1 2 | 3 4
+Card 2: 1 2 3 | 1 2 3"#,
+        )
+        .unwrap();
+        let cascade = cascade(&cards, &Doubling, Overflow::Clamp).unwrap();
+        assert_eq!(cascade.cards[1].clamped, 3);
+        assert_eq!(cascade.total(), Ok(2));
+        assert!(super::cascade(&cards, &Doubling, Overflow::Error).is_err());
     }
 }
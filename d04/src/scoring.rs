@@ -0,0 +1,118 @@
+// How a card's matches turn into points (part 1) and into copies of other
+// cards (part 2). The defaults are the puzzle's rules, so a rule only needs
+// to override the half it changes.
+pub trait ScoringRule {
+    // the first match is worth 1 point and every further match doubles it.
+    // None if that's too many points for a u64.
+    fn points(&self, matches: usize) -> Option<u64> {
+        match matches {
+            0 => Some(0),
+            n => u32::try_from(n - 1).ok().and_then(|n| 1u64.checked_shl(n)),
+        }
+    }
+
+    // indexes of the cards that one instance of card `card` wins a copy of,
+    // out of `cards` cards. Indexes past the end are left for the caller to
+    // clamp or reject.
+    fn wins(&self, card: usize, matches: usize, _cards: usize) -> Vec<usize> {
+        (card + 1..=card + matches).collect()
+    }
+}
+
+pub struct Doubling;
+
+impl ScoringRule for Doubling {}
+
+// one point per match
+pub struct Linear;
+
+impl ScoringRule for Linear {
+    fn points(&self, matches: usize) -> Option<u64> {
+        Some(matches as u64)
+    }
+}
+
+// 1, 2, 3, 5, 8, ... points for 1, 2, 3, 4, 5, ... matches
+pub struct Fibonacci;
+
+impl ScoringRule for Fibonacci {
+    fn points(&self, matches: usize) -> Option<u64> {
+        let (mut a, mut b) = (0u64, 1u64);
+        for _ in 0..matches {
+            (a, b) = (b, a.checked_add(b)?);
+        }
+        if matches == 0 {
+            Some(0)
+        } else {
+            Some(b)
+        }
+    }
+}
+
+// copies of the `matches` cards starting `ahead` cards on, wrapping round to
+// the first card instead of running off the end of the table
+pub struct WrapAround {
+    pub ahead: usize,
+}
+
+impl ScoringRule for WrapAround {
+    fn wins(&self, card: usize, matches: usize, cards: usize) -> Vec<usize> {
+        (0..matches)
+            .map(|k| (card + self.ahead + k) % cards)
+            .collect()
+    }
+}
+
+// doubling | linear | fibonacci | wrap:N
+pub fn parse(input: &str) -> Result<Box<dyn ScoringRule>, String> {
+    match input.split_once(':') {
+        None if input == "doubling" => Ok(Box::new(Doubling)),
+        None if input == "linear" => Ok(Box::new(Linear)),
+        None if input == "fibonacci" => Ok(Box::new(Fibonacci)),
+        Some(("wrap", ahead)) => ahead
+            .parse()
+            .map(|ahead| Box::new(WrapAround { ahead }) as Box<dyn ScoringRule>)
+            .map_err(|e| format!("invalid rule {:?}: {}", input, e)),
+        _ => Err(format!("unknown scoring rule {:?}", input)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_points() {
+        let points =
+            |rule: &dyn ScoringRule| (0..6).map(|m| rule.points(m).unwrap()).collect::<Vec<_>>();
+        assert_eq!(points(&Doubling), vec![0, 1, 2, 4, 8, 16]);
+        assert_eq!(points(&Linear), vec![0, 1, 2, 3, 4, 5]);
+        assert_eq!(points(&Fibonacci), vec![0, 1, 2, 3, 5, 8]);
+    }
+
+    #[test]
+    fn test_too_many_points() {
+        assert_eq!(Doubling.points(64), Some(1 << 63));
+        assert_eq!(Doubling.points(65), None);
+        assert_eq!(Doubling.points(usize::MAX), None);
+        // the 93rd Fibonacci number is the last that fits in a u64
+        assert_eq!(Fibonacci.points(92), Some(12200160415121876738));
+        assert_eq!(Fibonacci.points(93), None);
+        assert_eq!(Linear.points(1000), Some(1000));
+    }
+
+    #[test]
+    fn test_wins() {
+        assert_eq!(Doubling.wins(4, 3, 6), vec![5, 6, 7]);
+        assert_eq!(WrapAround { ahead: 1 }.wins(4, 3, 6), vec![5, 0, 1]);
+        assert_eq!(WrapAround { ahead: 3 }.wins(1, 2, 6), vec![4, 5]);
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse("linear").unwrap().points(3), Some(3));
+        assert_eq!(parse("wrap:2").unwrap().wins(0, 1, 3), vec![2]);
+        assert!(parse("wrap:x").is_err());
+        assert!(parse("quadratic").is_err());
+    }
+}
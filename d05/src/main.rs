@@ -1,18 +1,88 @@
-use std::{ops::Range, cmp::min};
+mod piecewise;
+mod validate;
+
+use std::{collections::HashSet, ops::Range};
 
 use nom::{
-    bytes::complete::{tag, take_until},
-    character::complete::{digit1, line_ending, multispace0, space1},
+    bytes::complete::tag,
+    character::complete::{alpha1, digit1, line_ending, multispace0, space1},
     combinator::map_res,
     multi::separated_list1,
-    sequence::preceded,
+    sequence::{preceded, separated_pair},
     IResult,
 };
 
+use piecewise::Piecewise;
+
 fn main() {
     let input = include_str!("../input.txt");
-    println!("Part 1: {}", lowest_seed_number(input));
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        // cargo run -- compose [from] [to]
+        ["compose", ..] => {
+            let almanac = or_exit(Almanac::parse(input));
+            let from = args.get(1).map_or("seed", String::as_str);
+            let to = args.get(2).map_or("location", String::as_str);
+            for piece in or_exit(almanac.compose(from, to)).pieces() {
+                println!("{:?} -> {:?}", piece.source, piece.destination_range());
+            }
+            return;
+        }
+        // cargo run -- validate
+        ["validate"] => {
+            let (_, almanac) = parse(input).unwrap();
+            for issue in validate::validate(&almanac) {
+                println!("{}", issue);
+            }
+            return;
+        }
+        // cargo run -- convert <from> <to> <value>
+        ["convert", from, to, value] => {
+            let value = or_exit(number(value));
+            let almanac = or_exit(Almanac::parse(input));
+            println!("{}", or_exit(almanac.convert(from, to, value)));
+            return;
+        }
+        // cargo run -- preimage <location start> <location end>
+        ["preimage", start, end] => {
+            let (start, end) = (or_exit(number(start)), or_exit(number(end)));
+            let almanac = or_exit(Almanac::parse(input));
+            let composed = or_exit(almanac.compose("seed", "location"));
+            for seeds in composed.preimage(start..end) {
+                println!("{:?}", seeds);
+            }
+            return;
+        }
+        _ => {}
+    }
+    for (part, result) in [
+        ("Part 1", lowest_seed_number(input)),
+        ("Part 2", part2(input)),
+    ] {
+        match result {
+            Ok(location) => println!("{}: {}", part, location),
+            Err(e) => {
+                eprintln!("{}: {}", part, e);
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+// prints the error and exits, for the subcommands
+fn or_exit<T>(result: Result<T, String>) -> T {
+    result.unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    })
+}
+
+// a number given on the command line
+fn number(arg: &str) -> Result<usize, String> {
+    arg.parse()
+        .map_err(|e| format!("invalid number {:?}: {}", arg, e))
 }
+
 // each map can be represented an addition operation
 // <destination> = input + C
 // where C = <destination range start> - <source range start>
@@ -42,9 +112,12 @@ impl Lookup {
     // returning an Option allows us to use a match statement later on
     // if the input is within the range of the lookup then return the destination
     // else return None
+    // the source range is source_start..source_start + length, so the end is
+    // exclusive. Subtracting first keeps the arithmetic inside the two ranges,
+    // which `validate` has already checked don't overflow
     fn get_destination(&self, input: usize) -> Option<usize> {
-        if input >= self.source_start && input <= self.source_start + self.length {
-            Some(input + self.destination_start - self.source_start)
+        if input >= self.source_start && input - self.source_start < self.length {
+            Some(input - self.source_start + self.destination_start)
         } else {
             None
         }
@@ -68,87 +141,203 @@ impl Lookup {
     }
 }
 
-// first let's parse the input into a vec of Lookups
-// the output vector will be a vec of vecs of Lookups
-// where
-// index 0 = seed-to-soil map
-// index 1 = soil-to-fertilizer map
-// index 2 = fertilizer-to-water map
-// index 3 = water-to-light map
-// index 4 = light-to-temperature map
-// index 5 = temperature-to-humidity map
-// index 6 = humidity-to-location map
-fn parse(input: &str) -> IResult<&str, (Vec<usize>, Vec<Vec<Lookup>>)> {
-    let (input, seeds) = preceded(
-        tag("seeds: "),
-        separated_list1(space1, map_res(digit1, |d: &str| d.parse::<usize>())),
-    )(input)?;
-    let (input, _) = multispace0(input)?;
-    let (input, maps) = separated_list1(tag("\n\n"), parse_lookup)(input)?;
-    Ok((input, (seeds, maps)))
+// each map in the almanac converts one category into the next, e.g. the
+// seed-to-soil map converts seed numbers into soil numbers. The maps chain
+// up, so any category can be converted into any later one by running through
+// all the maps in between
+#[derive(Debug)]
+struct Map {
+    source: String,
+    destination: String,
+    lookups: Vec<Lookup>,
+    // line number of the map's header in the almanac, for error messages
+    line: usize,
 }
 
-fn parse_lookup(input: &str) -> IResult<&str, Vec<Lookup>> {
-    let (input, _) = take_until("\n")(input)?;
-    let (input, lookups) = separated_list1(line_ending, Lookup::parse)(input)?;
-    Ok((input, lookups))
+impl Map {
+    // seed-to-soil map:
+    // 50 98 2
+    // 52 50 48
+    fn parse(input: &str) -> IResult<&str, Self> {
+        let (input, (source, destination)) = separated_pair(alpha1, tag("-to-"), alpha1)(input)?;
+        let (input, _) = tag(" map:")(input)?;
+        let (input, lookups) = separated_list1(line_ending, Lookup::parse)(input)?;
+        Ok((
+            input,
+            Map {
+                source: source.to_string(),
+                destination: destination.to_string(),
+                lookups,
+                line: 0,
+            },
+        ))
+    }
+
+    fn get(&self, value: usize) -> usize {
+        // the first lookup that covers the value wins, otherwise it stays
+        // the same as it was
+        self.lookups
+            .iter()
+            .find_map(|lookup| lookup.get_destination(value))
+            .unwrap_or(value)
+    }
 }
 
-fn seed_to_location(seed: usize, lookups: &Vec<Vec<Lookup>>) -> usize {
-    // all we need to do is mutate the seed number based on the lookups
+#[derive(Debug)]
+struct Almanac {
+    seeds: Vec<usize>,
+    maps: Vec<Map>,
+}
 
-    let mut mapped_value = seed;
-    for i in 0..lookups.len() {
-        for j in 0..lookups[i].len() {
-            // if the seed is within the range of the lookup then update it
-            if let Some(destination) = lookups[i][j].get_destination(mapped_value) {
-                mapped_value = destination;
-                break; // break out of the inner loop since we've found the destination
-            }
-            // else it stays the same as it was
+impl Almanac {
+    fn parse(input: &str) -> Result<Self, String> {
+        let (_, almanac) = parse(input).map_err(|e| format!("could not parse almanac: {}", e))?;
+        almanac.validate_chain()?;
+        let errors = validate::validate(&almanac)
+            .into_iter()
+            .filter(|issue| issue.is_error())
+            .map(|issue| issue.to_string())
+            .collect::<Vec<_>>();
+        if !errors.is_empty() {
+            return Err(errors.join("\n"));
         }
+        Ok(almanac)
     }
-    mapped_value
-}
 
-fn seed_range_to_location(seed_range: Range<usize>, list_of_lookups: &Vec<Vec<Lookup>>) -> usize {
-    let mut next_ranges: Vec<Range<usize>> = vec![seed_range];
-    let mut current_ranges: Vec<Range<usize>> = vec![];
-    list_of_lookups.iter().for_each(|lookups|{
-        for j in 0..lookups.len() {
-            current_ranges = next_ranges;
-            next_ranges = vec![];
-            current_ranges.iter().for_each(|r: &Range<usize>| {
-                let before = r.start..min(r.end, lookups[j].destination_start);
-                let middle = max(r.start, lookups[j].)
-
-
-            });
-            // if the seed is within the range of the lookup then update it
-            if let Some(destination) = list_of_lookups[i][j].get_destination(mapped_value) {
-                mapped_value = destination;
-                break; // break out of the inner loop since we've found the destination
+    // every map has to pick up where the previous one left off, so there's
+    // exactly one way to get from one category to another
+    fn validate_chain(&self) -> Result<(), String> {
+        for pair in self.maps.windows(2) {
+            if pair[0].destination != pair[1].source {
+                return Err(format!(
+                    "{}-to-{} map is followed by {}-to-{} map",
+                    pair[0].source, pair[0].destination, pair[1].source, pair[1].destination
+                ));
+            }
+        }
+        let mut seen = HashSet::new();
+        for category in self
+            .maps
+            .iter()
+            .map(|m| &m.source)
+            .chain(self.maps.last().map(|m| &m.destination))
+        {
+            if !seen.insert(category) {
+                return Err(format!("category {} appears twice in the chain", category));
             }
-            // else it stays the same as it was
         }
-    });
-    mapped_value
+        Ok(())
+    }
+
+    // the maps that convert `from` into `to`, in order
+    fn path(&self, from: &str, to: &str) -> Result<&[Map], String> {
+        let start = self.maps.iter().position(|m| m.source == from);
+        let end = self.maps.iter().position(|m| m.destination == to);
+        match (start, end) {
+            (Some(start), Some(end)) if start <= end => Ok(&self.maps[start..=end]),
+            // converting a category to itself needs no maps
+            _ if from == to && self.categories().any(|c| c == from) => Ok(&[]),
+            _ => Err(format!("no chain of maps converts {} into {}", from, to)),
+        }
+    }
+
+    fn categories(&self) -> impl Iterator<Item = &str> {
+        self.maps
+            .iter()
+            .map(|m| m.source.as_str())
+            .chain(self.maps.last().map(|m| m.destination.as_str()))
+    }
+
+    fn convert(&self, from: &str, to: &str, value: usize) -> Result<usize, String> {
+        match self.path(from, to) {
+            Ok(maps) => Ok(maps.iter().fold(value, |v, map| map.get(v))),
+            // going back up the chain only works if the maps are invertible
+            Err(e) => match self.compose(to, from) {
+                Ok(composed) => Ok(composed.inverse()?.get(value)),
+                Err(_) => Err(e),
+            },
+        }
+    }
+
+    // all the maps from `from` to `to` squashed into one
+    fn compose(&self, from: &str, to: &str) -> Result<Piecewise, String> {
+        Ok(self
+            .path(from, to)?
+            .iter()
+            .fold(Piecewise::identity(), |composed, map| {
+                composed.then(&Piecewise::from_lookups(&map.lookups))
+            }))
+    }
+}
+
+fn parse<'a>(input: &'a str) -> IResult<&'a str, Almanac> {
+    let full = input;
+    let (input, seeds) = preceded(
+        tag("seeds: "),
+        separated_list1(space1, map_res(digit1, |d: &str| d.parse::<usize>())),
+    )(input)?;
+    let (input, _) = multispace0(input)?;
+    let (input, maps) = separated_list1(tag("\n\n"), |i: &'a str| {
+        let (rest, mut map) = Map::parse(i)?;
+        map.line = full[..full.len() - i.len()].matches('\n').count() + 1;
+        Ok((rest, map))
+    })(input)?;
+    Ok((input, Almanac { seeds, maps }))
+}
+
+fn seed_to_location(seed: usize, seed_to_location: &Piecewise) -> usize {
+    seed_to_location.get(seed)
 }
 
-fn lowest_seed_number(input: &str) -> usize {
-    let (_, (seeds, lookups)) = parse(input).unwrap();
-    seeds.iter().fold(usize::MAX, |acc, x| {
-        let new = seed_to_location(x.clone(), &lookups);
+// for part 2 there are far too many seeds to map one at a time, so we map
+// whole ranges instead
+fn seed_range_to_location(seed_range: Range<usize>, seed_to_location: &Piecewise) -> usize {
+    // the lowest location in any range is its start
+    seed_to_location
+        .image(seed_range)
+        .iter()
+        .map(|r| r.start)
+        .min()
+        .unwrap_or(usize::MAX)
+}
+
+fn lowest_seed_number(input: &str) -> Result<usize, String> {
+    let almanac = Almanac::parse(input)?;
+    let composed = almanac.compose("seed", "location")?;
+    Ok(almanac.seeds.iter().fold(usize::MAX, |acc, x| {
+        let new = seed_to_location(*x, &composed);
         if new < acc {
             new
         } else {
             acc
         }
-    })
+    }))
 }
 
-fn part2(input: &str) -> usize {
-    todo!()
+fn part2(input: &str) -> Result<usize, String> {
+    let almanac = Almanac::parse(input)?;
+    let composed = almanac.compose("seed", "location")?;
+    // the seeds line is now pairs of <range start> <range length>
+    let pairs = almanac.seeds.chunks_exact(2);
+    if let [seed] = pairs.remainder() {
+        return Err(format!("seed {} has no range length", seed));
+    }
+    pairs
+        .map(|pair| {
+            let end = pair[0].checked_add(pair[1]).ok_or_else(|| {
+                format!(
+                    "seed range {} {} runs past {}",
+                    pair[0],
+                    pair[1],
+                    usize::MAX
+                )
+            })?;
+            Ok(seed_range_to_location(pair[0]..end, &composed))
+        })
+        .collect::<Result<Vec<_>, String>>()?
+        .into_iter()
+        .min()
+        .ok_or_else(|| "there are no seed ranges".to_string())
 }
 
 #[cfg(test)]
@@ -158,12 +347,142 @@ mod tests {
     #[test]
     fn test_lowest_seed_number() {
         let input = include_str!("../example.txt");
-        assert_eq!(lowest_seed_number(input), 35);
+        assert_eq!(lowest_seed_number(input), Ok(35));
     }
 
     #[test]
     fn test_part2() {
-        let input = include_str!("../input.txt");
-        assert_eq!(part2(input), 46);
+        let input = include_str!("../example.txt");
+        assert_eq!(part2(input), Ok(46));
+    }
+
+    #[test]
+    fn test_bad_seed_ranges() {
+        let input = include_str!("../example.txt");
+        let odd = input.replacen("seeds: 79 14 55 13", "seeds: 79 14 55", 1);
+        assert_eq!(part2(&odd), Err("seed 55 has no range length".to_string()));
+        // part 1 doesn't care
+        assert!(lowest_seed_number(&odd).is_ok());
+        let long = input.replacen("seeds: 79 14", &format!("seeds: {} 14", usize::MAX - 1), 1);
+        assert!(part2(&long).is_err_and(|e| e.contains("runs past")));
+    }
+
+    #[test]
+    fn test_seed_range_splitting() {
+        let almanac = Almanac::parse(include_str!("../example.txt")).unwrap();
+        let composed = almanac.compose("seed", "location").unwrap();
+        // single seeds from the part 1 walkthrough
+        assert_eq!(seed_range_to_location(79..80, &composed), 82);
+        assert_eq!(seed_range_to_location(14..15, &composed), 43);
+        assert_eq!(seed_range_to_location(55..56, &composed), 86);
+        assert_eq!(seed_range_to_location(13..14, &composed), 35);
+        assert_eq!(seed_range_to_location(79..93, &composed), 46);
+        assert_eq!(seed_range_to_location(55..68, &composed), 56);
+    }
+
+    #[test]
+    fn test_huge_seed_range() {
+        // billions of seeds shouldn't take any longer than a handful
+        let almanac = Almanac {
+            seeds: vec![],
+            maps: vec![Map {
+                source: "seed".to_string(),
+                destination: "location".to_string(),
+                lookups: vec![Lookup {
+                    destination_start: 0,
+                    source_start: 5_000_000_000,
+                    length: 1_000_000_000,
+                }],
+                line: 3,
+            }],
+        };
+        let composed = almanac.compose("seed", "location").unwrap();
+        assert_eq!(seed_range_to_location(10..10_000_000_000, &composed), 0);
+        assert_eq!(seed_range_to_location(10..5_000_000_000, &composed), 10);
+    }
+
+    #[test]
+    fn test_inverse_composition() {
+        let almanac = Almanac::parse(include_str!("../example.txt")).unwrap();
+        let composed = almanac.compose("seed", "location").unwrap();
+        let inverse = composed.inverse().unwrap();
+        // the part 2 answer: location 46 comes from seed 82
+        assert_eq!(inverse.get(46), 82);
+        for seed in [79, 14, 55, 13] {
+            assert_eq!(inverse.get(composed.get(seed)), seed);
+        }
+        // which seeds land in locations 46..47
+        assert_eq!(composed.preimage(46..47), vec![82..83]);
+        let soil_to_humidity = almanac.compose("soil", "humidity").unwrap();
+        assert_eq!(soil_to_humidity.get(81), 78);
+    }
+
+    #[test]
+    fn test_almanac_categories() {
+        let almanac = Almanac::parse(include_str!("../example.txt")).unwrap();
+        assert_eq!(
+            almanac.categories().collect::<Vec<_>>(),
+            vec![
+                "seed",
+                "soil",
+                "fertilizer",
+                "water",
+                "light",
+                "temperature",
+                "humidity",
+                "location"
+            ]
+        );
+        // seed 79 -> soil 81 -> ... -> humidity 78 -> location 82
+        assert_eq!(almanac.convert("soil", "humidity", 81), Ok(78));
+        assert_eq!(almanac.convert("seed", "location", 79), Ok(82));
+        assert_eq!(almanac.convert("light", "light", 74), Ok(74));
+        assert_eq!(almanac.convert("humidity", "soil", 78), Ok(81));
+        assert!(almanac.convert("seed", "sunlight", 79).is_err());
+    }
+
+    #[test]
+    fn test_broken_chain() {
+        let input = "seeds: 1\n\nseed-to-soil map:\n1 2 3\n\nwater-to-light map:\n1 2 3";
+        assert_eq!(
+            Almanac::parse(input).unwrap_err(),
+            "seed-to-soil map is followed by water-to-light map"
+        );
+        let input = "seeds: 1\n\nseed-to-soil map:\n1 2 3\n\nsoil-to-seed map:\n1 2 3";
+        assert!(Almanac::parse(input).is_err());
+    }
+
+    #[test]
+    fn test_lookup_range_end_is_exclusive() {
+        let lookup = Lookup {
+            destination_start: 50,
+            source_start: 98,
+            length: 2,
+        };
+        assert_eq!(lookup.get_destination(97), None);
+        assert_eq!(lookup.get_destination(98), Some(50));
+        assert_eq!(lookup.get_destination(99), Some(51));
+        assert_eq!(lookup.get_destination(100), None);
+    }
+
+    #[test]
+    fn test_composition_matches_walking_the_maps() {
+        let almanac = Almanac::parse(include_str!("../example.txt")).unwrap();
+        let composed = almanac.compose("seed", "location").unwrap();
+        for seed in 0..200 {
+            assert_eq!(
+                composed.get(seed),
+                almanac.convert("seed", "location", seed).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_almanac() {
+        let input = "seeds: 1\n\nseed-to-soil map:\n0 10 5\n0 12 5";
+        assert_eq!(
+            Almanac::parse(input).unwrap_err(),
+            "line 5: source range overlaps line 4"
+        );
     }
 }
@@ -0,0 +1,308 @@
+use std::{
+    cmp::{max, min},
+    ops::Range,
+};
+
+use crate::Lookup;
+
+// every value in `source` is shifted so that source.start lands on `destination`
+#[derive(Debug, PartialEq, Clone)]
+pub struct Piece {
+    pub source: Range<usize>,
+    pub destination: usize,
+}
+
+impl Piece {
+    fn get(&self, value: usize) -> usize {
+        value - self.source.start + self.destination
+    }
+
+    pub fn destination_range(&self) -> Range<usize> {
+        self.destination..self.destination.saturating_add(self.source.len())
+    }
+
+    // the part of this piece whose destination falls inside `range`
+    fn restrict_to_destination(&self, range: &Range<usize>) -> Option<Piece> {
+        let overlap = overlap(&self.destination_range(), range)?;
+        let start = self.source.start + (overlap.start - self.destination);
+        Some(Piece {
+            source: start..start + overlap.len(),
+            destination: overlap.start,
+        })
+    }
+}
+
+// A whole chain of maps squashed into one. The pieces are sorted by source and
+// cover 0..usize::MAX without gaps, so looking a value up is a binary search
+// for the piece that contains it.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Piecewise {
+    pieces: Vec<Piece>,
+}
+
+impl Piecewise {
+    pub fn identity() -> Self {
+        Piecewise {
+            pieces: vec![Piece {
+                source: 0..usize::MAX,
+                destination: 0,
+            }],
+        }
+    }
+
+    // a single map, where the first lookup that covers a value wins and
+    // anything no lookup covers maps to itself
+    pub fn from_lookups(lookups: &[Lookup]) -> Self {
+        // between two consecutive lookup boundaries the same lookup applies
+        let mut bounds = vec![0, usize::MAX];
+        for lookup in lookups {
+            bounds.push(lookup.source_start);
+            bounds.push(lookup.source_start.saturating_add(lookup.length));
+        }
+        bounds.sort();
+        bounds.dedup();
+        let pieces = bounds
+            .windows(2)
+            .map(|w| {
+                let destination = lookups
+                    .iter()
+                    .find(|l| {
+                        l.source_start <= w[0] && w[0] < l.source_start.saturating_add(l.length)
+                    })
+                    .map_or(w[0], |l| w[0] - l.source_start + l.destination_start);
+                Piece {
+                    source: w[0]..w[1],
+                    destination,
+                }
+            })
+            .collect();
+        Piecewise { pieces }.merged()
+    }
+
+    pub fn pieces(&self) -> &[Piece] {
+        &self.pieces
+    }
+
+    pub fn get(&self, value: usize) -> usize {
+        let i = self.pieces.partition_point(|p| p.source.end <= value);
+        self.pieces.get(i).map_or(value, |p| p.get(value))
+    }
+
+    // this map followed by `next`. Each piece's destination range gets split
+    // wherever it crosses a boundary between the pieces of `next`:
+    //
+    //   destinations:  |-----------------|
+    //   next:        ---|----|------|-------
+    //                  |-|----|------|---|
+    //
+    // and every split piece goes straight to where `next` sends it
+    pub fn then(&self, next: &Piecewise) -> Piecewise {
+        let mut pieces = vec![];
+        for piece in &self.pieces {
+            let destinations = piece.destination_range();
+            let first = next
+                .pieces
+                .partition_point(|p| p.source.end <= destinations.start);
+            for next_piece in next.pieces[first..]
+                .iter()
+                .take_while(|p| p.source.start < destinations.end)
+            {
+                if let Some(part) = piece.restrict_to_destination(&next_piece.source) {
+                    pieces.push(Piece {
+                        destination: next_piece.get(part.destination),
+                        source: part.source,
+                    });
+                }
+            }
+        }
+        Piecewise { pieces }.merged()
+    }
+
+    // where the values in `range` end up, in source order
+    pub fn image(&self, range: Range<usize>) -> Vec<Range<usize>> {
+        let first = self.pieces.partition_point(|p| p.source.end <= range.start);
+        self.pieces[first..]
+            .iter()
+            .take_while(|p| p.source.start < range.end)
+            .filter_map(|p| {
+                let overlap = overlap(&p.source, &range)?;
+                Some(p.get(overlap.start)..p.get(overlap.start) + overlap.len())
+            })
+            .collect()
+    }
+
+    // every value that ends up in `range`, sorted and merged
+    pub fn preimage(&self, range: Range<usize>) -> Vec<Range<usize>> {
+        let mut sources = self
+            .pieces
+            .iter()
+            .filter_map(|p| p.restrict_to_destination(&range))
+            .map(|p| p.source)
+            .collect::<Vec<_>>();
+        sources.sort_by_key(|r| r.start);
+        sources.into_iter().fold(vec![], |mut merged, r| {
+            match merged.last_mut() {
+                Some(last) if last.end == r.start => last.end = r.end,
+                _ => merged.push(r),
+            }
+            merged
+        })
+    }
+
+    // the map running backwards, which only exists if every destination is
+    // reached from exactly one source
+    pub fn inverse(&self) -> Result<Piecewise, String> {
+        let mut pieces = self
+            .pieces
+            .iter()
+            .map(|p| Piece {
+                source: p.destination_range(),
+                destination: p.source.start,
+            })
+            .collect::<Vec<_>>();
+        pieces.sort_by_key(|p| p.source.start);
+        let mut covered = 0;
+        for piece in &pieces {
+            match piece.source.start.cmp(&covered) {
+                std::cmp::Ordering::Less => {
+                    return Err(format!(
+                        "{}..{} is reached from more than one source",
+                        piece.source.start,
+                        min(covered, piece.source.end)
+                    ))
+                }
+                std::cmp::Ordering::Greater => {
+                    return Err(format!(
+                        "{}..{} is not reached from any source",
+                        covered, piece.source.start
+                    ))
+                }
+                std::cmp::Ordering::Equal => covered = piece.source.end,
+            }
+        }
+        if covered != usize::MAX {
+            return Err(format!(
+                "{}..{} is not reached from any source",
+                covered,
+                usize::MAX
+            ));
+        }
+        Ok(Piecewise { pieces }.merged())
+    }
+
+    // join neighbouring pieces that shift by the same amount
+    fn merged(self) -> Self {
+        let mut pieces: Vec<Piece> = vec![];
+        for piece in self.pieces {
+            match pieces.last_mut() {
+                Some(last)
+                    if last.source.end == piece.source.start
+                        && last.destination_range().end == piece.destination =>
+                {
+                    last.source.end = piece.source.end
+                }
+                _ => pieces.push(piece),
+            }
+        }
+        Piecewise { pieces }
+    }
+}
+
+fn overlap(a: &Range<usize>, b: &Range<usize>) -> Option<Range<usize>> {
+    let overlap = max(a.start, b.start)..min(a.end, b.end);
+    if overlap.is_empty() {
+        None
+    } else {
+        Some(overlap)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup(destination_start: usize, source_start: usize, length: usize) -> Lookup {
+        Lookup {
+            destination_start,
+            source_start,
+            length,
+        }
+    }
+
+    #[test]
+    fn test_from_lookups() {
+        // seed-to-soil map from the example
+        let map = Piecewise::from_lookups(&[lookup(50, 98, 2), lookup(52, 50, 48)]);
+        assert_eq!(
+            map.pieces(),
+            [
+                Piece {
+                    source: 0..50,
+                    destination: 0
+                },
+                Piece {
+                    source: 50..98,
+                    destination: 52
+                },
+                Piece {
+                    source: 98..100,
+                    destination: 50
+                },
+                Piece {
+                    source: 100..usize::MAX,
+                    destination: 100
+                },
+            ]
+        );
+        assert_eq!(map.get(49), 49);
+        assert_eq!(map.get(79), 81);
+        assert_eq!(map.get(99), 51);
+        assert_eq!(map.get(100), 100);
+    }
+
+    #[test]
+    fn test_first_lookup_wins() {
+        let map = Piecewise::from_lookups(&[lookup(100, 10, 5), lookup(200, 0, 20)]);
+        assert_eq!(
+            (0..20).map(|v| map.get(v)).collect::<Vec<_>>(),
+            vec![
+                200, 201, 202, 203, 204, 205, 206, 207, 208, 209, 100, 101, 102, 103, 104, 215,
+                216, 217, 218, 219
+            ]
+        );
+    }
+
+    #[test]
+    fn test_then() {
+        let a = Piecewise::from_lookups(&[lookup(10, 0, 10)]);
+        let b = Piecewise::from_lookups(&[lookup(0, 15, 5)]);
+        let ab = a.then(&b);
+        for v in 0..40 {
+            assert_eq!(ab.get(v), b.get(a.get(v)));
+        }
+        // 0..5 -> 10..15 stays, 5..10 -> 15..20 -> 0..5
+        assert_eq!(ab.get(7), 2);
+    }
+
+    #[test]
+    fn test_image_and_preimage() {
+        let map = Piecewise::from_lookups(&[lookup(50, 98, 2), lookup(52, 50, 48)]);
+        assert_eq!(map.image(45..55), vec![45..50, 52..57]);
+        assert_eq!(map.preimage(49..53), vec![49..51, 98..100]);
+    }
+
+    #[test]
+    fn test_inverse() {
+        let map = Piecewise::from_lookups(&[lookup(50, 98, 2), lookup(52, 50, 48)]);
+        let inverse = map.inverse().unwrap();
+        for v in 0..200 {
+            assert_eq!(inverse.get(map.get(v)), v);
+        }
+        // 0..5 and 5..10 both land on 0..5
+        let squashed = Piecewise::from_lookups(&[lookup(0, 5, 5)]);
+        assert_eq!(
+            squashed.inverse(),
+            Err("0..5 is reached from more than one source".to_string())
+        );
+    }
+}
@@ -0,0 +1,183 @@
+use std::{fmt, ops::Range};
+
+use crate::{Almanac, Lookup};
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Problem {
+    // the source range overlaps the one on another line of the same map
+    Overlap { line: usize },
+    // no lookup covers these values, so they map to themselves
+    Gap(Range<usize>),
+    ZeroLength,
+    Overflow(&'static str),
+}
+
+// a problem with one line of the almanac
+#[derive(Debug, PartialEq, Clone)]
+pub struct Issue {
+    pub line: usize,
+    pub problem: Problem,
+}
+
+impl Issue {
+    // gaps and empty lookups are harmless, everything else makes the
+    // mapping ambiguous or impossible to compute
+    pub fn is_error(&self) -> bool {
+        matches!(self.problem, Problem::Overlap { .. } | Problem::Overflow(_))
+    }
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.problem {
+            Problem::Overlap { line } => write!(f, "source range overlaps line {}", line),
+            Problem::Gap(range) => write!(f, "{:?} is not covered by any lookup", range),
+            Problem::ZeroLength => write!(f, "lookup has zero length"),
+            Problem::Overflow(what) => write!(f, "{} overflows", what),
+        }
+    }
+}
+
+// check every map's lookups before anything gets mapped
+pub fn validate(almanac: &Almanac) -> Vec<Issue> {
+    let mut issues = vec![];
+    for map in &almanac.maps {
+        // lookups start on the line after the map's header
+        let lines = (map.line + 1..).zip(&map.lookups).collect::<Vec<_>>();
+        for (line, lookup) in &lines {
+            issues.extend(lookup_problems(lookup).into_iter().map(|problem| Issue {
+                line: *line,
+                problem,
+            }));
+        }
+
+        // sorted by where they start, a lookup overlaps the previous one if it
+        // starts before the furthest end seen so far, and leaves a gap if it
+        // starts after it
+        let mut sorted = lines
+            .iter()
+            .filter(|(_, l)| l.length > 0)
+            .map(|(line, l)| {
+                (
+                    *line,
+                    l.source_start,
+                    l.source_start.saturating_add(l.length),
+                )
+            })
+            .collect::<Vec<_>>();
+        sorted.sort_by_key(|(line, start, _)| (*start, *line));
+        let mut furthest: Option<(usize, usize)> = None;
+        for (line, start, end) in sorted {
+            match furthest {
+                Some((furthest_line, furthest_end)) if start < furthest_end => issues.push(Issue {
+                    line,
+                    problem: Problem::Overlap {
+                        line: furthest_line,
+                    },
+                }),
+                Some((_, furthest_end)) if start > furthest_end => issues.push(Issue {
+                    line,
+                    problem: Problem::Gap(furthest_end..start),
+                }),
+                _ => {}
+            }
+            if furthest.is_none_or(|(_, furthest_end)| end > furthest_end) {
+                furthest = Some((line, end));
+            }
+        }
+    }
+    issues.sort_by_key(|issue| issue.line);
+    issues
+}
+
+fn lookup_problems(lookup: &Lookup) -> Vec<Problem> {
+    let mut problems = vec![];
+    if lookup.length == 0 {
+        problems.push(Problem::ZeroLength);
+    }
+    if lookup.source_start.checked_add(lookup.length).is_none() {
+        problems.push(Problem::Overflow("source_start + length"));
+    }
+    if lookup
+        .destination_start
+        .checked_add(lookup.length)
+        .is_none()
+    {
+        problems.push(Problem::Overflow("destination_start + length"));
+    }
+    // the shift C = destination_start - source_start has to fit in a signed number
+    let shift = lookup.destination_start as i128 - lookup.source_start as i128;
+    if isize::try_from(shift).is_err() {
+        problems.push(Problem::Overflow("destination_start - source_start"));
+    }
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    fn issues(input: &str) -> Vec<Issue> {
+        let (_, almanac) = parse(input).unwrap();
+        validate(&almanac)
+    }
+
+    #[test]
+    fn test_example_is_valid() {
+        assert_eq!(issues(include_str!("../example.txt")), vec![]);
+    }
+
+    #[test]
+    fn test_overlap_gap_and_zero_length() {
+        let input = "seeds: 1
+
+seed-to-soil map:
+0 10 5
+0 12 5
+100 20 5
+7 7 0";
+        assert_eq!(
+            issues(input),
+            vec![
+                Issue {
+                    line: 5,
+                    problem: Problem::Overlap { line: 4 }
+                },
+                Issue {
+                    line: 6,
+                    problem: Problem::Gap(17..20)
+                },
+                Issue {
+                    line: 7,
+                    problem: Problem::ZeroLength
+                },
+            ]
+        );
+        assert_eq!(
+            issues(input)[0].to_string(),
+            "line 5: source range overlaps line 4"
+        );
+    }
+
+    #[test]
+    fn test_overflow() {
+        let input = "seeds: 1
+
+seed-to-soil map:
+18446744073709551615 0 2";
+        let issues = issues(input);
+        assert_eq!(
+            issues
+                .iter()
+                .map(|i| (i.line, i.problem.clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                (4, Problem::Overflow("destination_start + length")),
+                (4, Problem::Overflow("destination_start - source_start")),
+            ]
+        );
+        assert!(issues.iter().all(|i| i.is_error()));
+    }
+}
@@ -0,0 +1,247 @@
+use std::ops::RangeInclusive;
+
+// How far a boat gets in a race of `time` ms after holding the button for
+// `hold` ms, and from that which hold times beat the record
+pub trait BoatModel {
+    fn distance(&self, hold: u64, time: u64) -> u128;
+
+    // The hold times that beat `record`. This searches for the peak distance
+    // and then for the edges of the window either side of it, so it relies on
+    // the distance rising (or staying flat at the top) and then falling as
+    // the hold time grows, which every model here does.
+    fn window(&self, time: u64, record: u64) -> Option<RangeInclusive<u64>> {
+        let distance = |hold: u64| self.distance(hold, time);
+        let beats = |hold: u64| distance(hold) > record as u128;
+
+        // ternary search for the peak
+        let (mut lo, mut hi) = (0, time);
+        while hi - lo > 2 {
+            let m1 = lo + (hi - lo) / 3;
+            let m2 = hi - (hi - lo) / 3;
+            if distance(m1) < distance(m2) {
+                lo = m1 + 1;
+            } else {
+                hi = m2;
+            }
+        }
+        let peak = (lo..=hi).max_by_key(|&hold| distance(hold)).unwrap();
+        if !beats(peak) {
+            return None;
+        }
+
+        // binary search down the rising side for the first winner...
+        let (mut lo, mut hi) = (0, peak);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if beats(mid) {
+                hi = mid;
+            } else {
+                lo = mid + 1;
+            }
+        }
+        let first = lo;
+        // ...and down the falling side for the last
+        let (mut lo, mut hi) = (peak, time);
+        while lo < hi {
+            let mid = lo + (hi - lo).div_ceil(2);
+            if beats(mid) {
+                lo = mid;
+            } else {
+                hi = mid - 1;
+            }
+        }
+        Some(first..=lo)
+    }
+}
+
+// The puzzle's boat: every ms holding the button adds 1 mm/ms of speed
+pub struct Linear;
+
+impl BoatModel for Linear {
+    fn distance(&self, hold: u64, time: u64) -> u128 {
+        hold as u128 * time.saturating_sub(hold) as u128
+    }
+
+    fn window(&self, time: u64, record: u64) -> Option<RangeInclusive<u64>> {
+        // we win when
+        //   h * (time - h) > record
+        //   h^2 - time * h + record < 0
+        // which holds strictly between the roots of the quadratic
+        //   h = (time +- sqrt(time^2 - 4 * record)) / 2
+        // f64 can't represent the part 2 numbers exactly, so do it all in u128
+        // with an integer square root and then nudge the estimate onto the
+        // exact boundary
+        let time = time as u128;
+        let record = record as u128;
+        let wins = |hold: u128| hold * (time - hold) > record;
+
+        // the distance peaks at half the race, so if that doesn't win nothing does
+        if !wins(time / 2) {
+            return None;
+        }
+        let discriminant = time * time - 4 * record;
+        let mut first = (time - discriminant.isqrt()) / 2;
+        // isqrt rounds down, which can leave us a step either side of the boundary
+        while !wins(first) {
+            first += 1;
+        }
+        while first > 0 && wins(first - 1) {
+            first -= 1;
+        }
+        // the winning hold times are symmetric about time / 2
+        Some(first as u64..=(time - first) as u64)
+    }
+}
+
+// Holding the button adds `rate` mm/ms of speed per ms, up to `max_speed`.
+// Once released the boat loses `drag` mm/ms of speed for every ms it travels
+// until it stops.
+pub struct Charged {
+    pub rate: u64,
+    pub max_speed: Option<u64>,
+    pub drag: u64,
+}
+
+impl BoatModel for Charged {
+    fn distance(&self, hold: u64, time: u64) -> u128 {
+        let mut speed = hold as u128 * self.rate as u128;
+        if let Some(max_speed) = self.max_speed {
+            speed = speed.min(max_speed as u128);
+        }
+        let travel = time.saturating_sub(hold) as u128;
+        if self.drag == 0 {
+            return speed * travel;
+        }
+        // the boat covers speed, speed - drag, speed - 2 * drag, ... mm in each
+        // ms until it either stops or runs out of time
+        let drag = self.drag as u128;
+        let moving = travel.min(speed.div_ceil(drag));
+        moving * speed - drag * moving * moving.saturating_sub(1) / 2
+    }
+}
+
+// rate=N cap=N drag=N, in any order, each defaulting to the puzzle's boat
+pub fn parse(args: &[String]) -> Result<Box<dyn BoatModel>, String> {
+    if args.is_empty() {
+        return Ok(Box::new(Linear));
+    }
+    let mut boat = Charged {
+        rate: 1,
+        max_speed: None,
+        drag: 0,
+    };
+    for arg in args {
+        let (key, value) = arg
+            .split_once('=')
+            .ok_or_else(|| format!("expected key=value, got {:?}", arg))?;
+        let value = value
+            .parse()
+            .map_err(|e| format!("invalid value in {:?}: {}", arg, e))?;
+        match key {
+            "rate" => boat.rate = value,
+            "cap" => boat.max_speed = Some(value),
+            "drag" => boat.drag = value,
+            _ => return Err(format!("unknown boat setting {:?}", key)),
+        }
+    }
+    Ok(Box::new(boat))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn brute_force(boat: &dyn BoatModel, time: u64, record: u64) -> Option<RangeInclusive<u64>> {
+        let winners = (0..=time)
+            .filter(|&hold| boat.distance(hold, time) > record as u128)
+            .collect::<Vec<_>>();
+        // every model here has one contiguous window
+        if let (Some(&first), Some(&last)) = (winners.first(), winners.last()) {
+            assert_eq!(winners.len() as u64, last - first + 1);
+            Some(first..=last)
+        } else {
+            None
+        }
+    }
+
+    #[test]
+    fn test_charged_distance() {
+        let boat = Charged {
+            rate: 2,
+            max_speed: Some(5),
+            drag: 2,
+        };
+        // speed 4: 4 + 2 then stopped
+        assert_eq!(boat.distance(2, 10), 6);
+        // speed capped at 5: 5 + 3 + 1 then stopped
+        assert_eq!(boat.distance(4, 10), 9);
+        // speed 5 but only 2 ms left: 5 + 3
+        assert_eq!(boat.distance(8, 10), 8);
+    }
+
+    #[test]
+    fn test_default_charged_is_linear() {
+        let boat = Charged {
+            rate: 1,
+            max_speed: None,
+            drag: 0,
+        };
+        for time in 0..40 {
+            for record in 0..time * time / 4 + 2 {
+                assert_eq!(boat.window(time, record), Linear.window(time, record));
+            }
+        }
+    }
+
+    #[test]
+    fn test_windows_match_brute_force() {
+        let boats = [
+            Charged {
+                rate: 3,
+                max_speed: None,
+                drag: 0,
+            },
+            Charged {
+                rate: 2,
+                max_speed: Some(7),
+                drag: 0,
+            },
+            Charged {
+                rate: 1,
+                max_speed: None,
+                drag: 1,
+            },
+            Charged {
+                rate: 4,
+                max_speed: Some(9),
+                drag: 2,
+            },
+        ];
+        for boat in &boats {
+            for time in 0..40 {
+                let best = (0..=time).map(|h| boat.distance(h, time)).max().unwrap() as u64;
+                for record in 0..best + 2 {
+                    assert_eq!(
+                        boat.window(time, record),
+                        brute_force(boat, time, record),
+                        "rate {} cap {:?} drag {} time {} record {}",
+                        boat.rate,
+                        boat.max_speed,
+                        boat.drag,
+                        time,
+                        record
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let boat = parse(&["cap=10".to_string(), "rate=2".to_string()]).unwrap();
+        assert_eq!(boat.distance(3, 10), 42);
+        assert_eq!(boat.distance(6, 10), 40);
+        assert!(parse(&["speed=1".to_string()]).is_err());
+        assert!(parse(&["rate".to_string()]).is_err());
+    }
+}
@@ -1,53 +1,98 @@
+mod boat;
+
+use std::ops::RangeInclusive;
+
 use nom::{
     bytes::complete::tag,
-    character::complete,
-    character::complete::space1,
+    character::complete::{digit1, space1},
+    error::ErrorKind,
     multi::separated_list1,
     sequence::{preceded, separated_pair},
     IResult,
 };
 
+use boat::{BoatModel, Linear};
+
 fn main() {
     let input = include_str!("../input.txt");
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some("report") = args.first().map(String::as_str) {
+        // cargo run -- report [rate=N] [cap=N] [drag=N]
+        let boat = match boat::parse(&args[1..]) {
+            Ok(boat) => boat,
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        };
+        for (i, race) in parse(input, &Kerning::Separate)
+            .unwrap()
+            .1
+            .iter()
+            .enumerate()
+        {
+            match race.window(boat.as_ref()) {
+                Some(window) => println!(
+                    "Race {}: time {}, record {}: hold for {:?} ms ({} ways)",
+                    i + 1,
+                    race.time,
+                    race.record,
+                    window,
+                    window.end() - window.start() + 1
+                ),
+                None => println!(
+                    "Race {}: time {}, record {}: can't be won",
+                    i + 1,
+                    race.time,
+                    race.record
+                ),
+            }
+        }
+        return;
+    }
+    if let Some("columns") = args.first().map(String::as_str) {
+        // cargo run -- columns 0,1 2,3
+        let kerning = match Kerning::parse(&args[1..]) {
+            Ok(kerning) => kerning,
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        };
+        match parse(input, &kerning) {
+            Ok((_, races)) => {
+                for race in &races {
+                    println!("{:?}: {} ways", race, ways_to_win(race));
+                }
+                println!("Product: {}", product_of_ways(input, &kerning));
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
     println!("Part 1: {}", part1(input));
     println!("Part 2: {}", part2(input));
 }
 
 fn part1(input: &str) -> usize {
-    let races = parse(input).unwrap().1;
-    let mut wins: Vec<u64> = vec![0; races.len()];
-
-    races.iter().enumerate().for_each(|(race_number, race)| {
-        wins[race_number] = ways_to_win(race);
-    });
-    wins.iter().product::<u64>() as usize
+    product_of_ways(input, &Kerning::Separate)
 }
 
-fn part2(input_: &str) -> usize {
-    let input = input_.replace(' ', "");
-    let input = input.strip_suffix('\n').unwrap_or(input.as_str());
-    let (_, (time, distance)) = parse_part2(input).unwrap();
+fn part2(input: &str) -> usize {
+    // the bad kerning means there's really only one race
+    product_of_ways(input, &Kerning::Concatenated)
+}
 
-    let race = Race {
-        time,
-        record: distance,
-    };
-    ways_to_win(&race) as usize
+fn product_of_ways(input: &str, kerning: &Kerning) -> usize {
+    let races = parse(input, kerning).unwrap().1;
+    races.iter().map(ways_to_win).product::<u64>() as usize
 }
 
 fn ways_to_win(race: &Race) -> u64 {
-    // quadratic formula
-    // x = -b +- sqrt(b^2 - 4ac) / 2a
-
-    let a = 1_f64;
-    let b = -(race.time as f64);
-    let c = race.record as f64;
-    // min = -b - sqrt(b^2 - 4ac) / 2a
-    // max = -b + sqrt(b^2 - 4ac) / 2a
-    let min_waiting_time = (-b - (b * b - 4.0 * a * c).sqrt()) / (2.0 * a) + 0.001; // add a tiny bit because if the root is a whole number then we need to round to the next whole number
-    let max_waiting_time = (-b + (b * b - 4.0 * a * c).sqrt()) / (2.0 * a) - 0.001; // same story
-
-    (max_waiting_time.floor() - min_waiting_time.ceil()) as u64 + 1
+    race.window(&Linear).map_or(0, |w| w.end() - w.start() + 1)
 }
 
 #[derive(Debug, PartialEq)]
@@ -56,43 +101,97 @@ struct Race {
     record: u64,
 }
 
+impl Race {
+    // the hold times that beat the record
+    fn window(&self, boat: &dyn BoatModel) -> Option<RangeInclusive<u64>> {
+        boat.window(self.time, self.record)
+    }
+}
+
 type Races = Vec<Race>;
 
-fn parse(input: &str) -> IResult<&str, Races> {
-    let (input, (times, distances)) = separated_pair(
+// How the columns of the sheet turn into races
+#[derive(Debug, PartialEq, Clone)]
+enum Kerning {
+    // every column is its own race
+    Separate,
+    // all the digits on each line make up a single race
+    Concatenated,
+    // each group of column indexes (from 0) joins into one race, in order
+    Groups(Vec<Vec<usize>>),
+}
+
+impl Kerning {
+    // groups like `0,1 2,3` from the command line
+    fn parse(args: &[String]) -> Result<Kerning, String> {
+        args.iter()
+            .map(|group| {
+                group
+                    .split(',')
+                    .map(|c| {
+                        c.parse()
+                            .map_err(|e| format!("invalid column {:?} in {:?}: {}", c, group, e))
+                    })
+                    .collect()
+            })
+            .collect::<Result<_, _>>()
+            .map(Kerning::Groups)
+    }
+
+    fn groups(&self, columns: usize) -> Vec<Vec<usize>> {
+        match self {
+            Kerning::Separate => (0..columns).map(|c| vec![c]).collect(),
+            Kerning::Concatenated => vec![(0..columns).collect()],
+            Kerning::Groups(groups) => groups.clone(),
+        }
+    }
+}
+
+fn parse<'a>(input: &'a str, kerning: &Kerning) -> IResult<&'a str, Races> {
+    let (rest, (times, distances)) = separated_pair(
         preceded(
             preceded(tag("Time:"), space1),
-            separated_list1(space1, complete::u64),
+            separated_list1(space1, digit1),
         ),
         tag("\n"),
         preceded(
             preceded(tag("Distance:"), space1),
-            separated_list1(space1, complete::u64),
+            separated_list1(space1, digit1),
         ),
     )(input)?;
-    let races = times
+    // numbers are joined as text, so the digits line up the way they look
+    let join = |columns: &[&str], group: &[usize]| -> Option<u64> {
+        group
+            .iter()
+            .map(|&c| columns.get(c).copied())
+            .collect::<Option<String>>()?
+            .parse()
+            .ok()
+    };
+    let failure = || nom::Err::Failure(nom::error::Error::new(input, ErrorKind::Verify));
+    if times.len() != distances.len() {
+        return Err(failure());
+    }
+    let races = kerning
+        .groups(times.len())
         .iter()
-        .zip(distances.iter())
-        .map(|(time, distance)| Race {
-            time: *time,
-            record: *distance,
+        .map(|group| {
+            Some(Race {
+                time: join(&times, group)?,
+                record: join(&distances, group)?,
+            })
         })
-        .collect();
-    Ok((input, races))
-}
-
-fn parse_part2(input: &str) -> IResult<&str, (u64, u64)> {
-    let (input, (time, distance)) = separated_pair(
-        preceded(tag("Time:"), complete::u64),
-        tag("\n"),
-        preceded(tag("Distance:"), complete::u64),
-    )(input)?;
-    Ok((input, (time, distance)))
+        .collect::<Option<Races>>()
+        // a group with no columns, a column that doesn't exist or a number too big for u64
+        .ok_or_else(failure)?;
+    Ok((rest, races))
 }
 
 #[cfg(test)]
 mod tests {
 
+    use rstest::rstest;
+
     use super::*;
 
     const EXAMPLE: &str = r#"Time:      7  15   30
@@ -106,7 +205,7 @@ Distance:  9  40  200"#;
     #[test]
     fn test_parse() {
         assert_eq!(
-            parse(EXAMPLE).unwrap().1,
+            parse(EXAMPLE, &Kerning::Separate).unwrap().1,
             vec![
                 Race { time: 7, record: 9 },
                 Race {
@@ -125,4 +224,108 @@ Distance:  9  40  200"#;
     fn test_part2() {
         assert_eq!(part2(EXAMPLE), 71503)
     }
+
+    fn brute_force(race: &Race) -> u64 {
+        (0..=race.time)
+            .filter(|hold| hold * (race.time - hold) > race.record)
+            .count() as u64
+    }
+
+    #[test]
+    fn test_kerning() {
+        assert_eq!(
+            parse(EXAMPLE, &Kerning::Concatenated).unwrap().1,
+            vec![Race {
+                time: 71530,
+                record: 940200
+            }]
+        );
+        assert_eq!(
+            parse(EXAMPLE, &Kerning::Groups(vec![vec![0, 1], vec![2]]))
+                .unwrap()
+                .1,
+            vec![
+                Race {
+                    time: 715,
+                    record: 940
+                },
+                Race {
+                    time: 30,
+                    record: 200
+                }
+            ]
+        );
+        assert_eq!(
+            product_of_ways(EXAMPLE, &Kerning::Groups(vec![vec![2], vec![0]])),
+            9 * 4
+        );
+    }
+
+    #[rstest]
+    #[case(Kerning::Groups(vec![vec![3]]))]
+    #[case(Kerning::Groups(vec![vec![]]))]
+    #[case(Kerning::Groups(vec![vec![0, 1, 2, 0, 1, 2, 0, 1, 2, 0, 1, 2]]))]
+    fn test_bad_kerning(#[case] kerning: Kerning) {
+        assert!(parse(EXAMPLE, &kerning).is_err());
+    }
+
+    #[test]
+    fn test_parse_kerning() {
+        let args = |args: &[&str]| args.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            Kerning::parse(&args(&["0,1", "2"])),
+            Ok(Kerning::Groups(vec![vec![0, 1], vec![2]]))
+        );
+        assert_eq!(
+            Kerning::parse(&args(&["0,1", "x"])),
+            Err("invalid column \"x\" in \"x\": invalid digit found in string".to_string())
+        );
+        assert!(Kerning::parse(&args(&["0,", "1"])).is_err());
+    }
+
+    #[test]
+    fn test_mismatched_columns() {
+        assert!(parse("Time: 1 2\nDistance: 3", &Kerning::Separate).is_err());
+    }
+
+    #[test]
+    fn test_race_windows() {
+        let races = parse(EXAMPLE, &Kerning::Separate).unwrap().1;
+        assert_eq!(
+            races.iter().map(|r| r.window(&Linear)).collect::<Vec<_>>(),
+            vec![Some(2..=5), Some(4..=11), Some(11..=19)]
+        );
+        assert_eq!(Race { time: 4, record: 4 }.window(&Linear), None);
+    }
+
+    #[test]
+    fn test_ways_to_win_matches_brute_force() {
+        for time in 0..60 {
+            for record in 0..time * time / 4 + 2 {
+                let race = Race { time, record };
+                assert_eq!(ways_to_win(&race), brute_force(&race), "{:?}", race);
+            }
+        }
+    }
+
+    const K: u64 = 4_000_000_000;
+
+    #[rstest]
+    // roots exactly on 10 and 20, which don't count
+    #[case(30, 200, 9)]
+    // discriminant of 4: the only winner is holding for exactly half
+    #[case(2 * K, K * K - 1, 1)]
+    // discriminant of 0: holding for half only draws
+    #[case(2 * K, K * K, 0)]
+    // perfect square discriminant with roots K +- 12345
+    #[case(2 * K, K * K - 12345 * 12345, 2 * 12345 - 1)]
+    // one below a perfect square, so the roots land just outside K +- 12345
+    #[case(2 * K, K * K - 12345 * 12345 - 1, 2 * 12345 + 1)]
+    // odd times peak on two hold times
+    #[case(2 * K + 1, K * (K + 1) - 1, 2)]
+    #[case(2 * K + 1, K * (K + 1), 0)]
+    #[case(u32::MAX as u64, 0, u32::MAX as u64 - 1)]
+    fn test_ways_to_win_exact(#[case] time: u64, #[case] record: u64, #[case] expected: u64) {
+        assert_eq!(ways_to_win(&Race { time, record }), expected);
+    }
 }
@@ -0,0 +1,15 @@
+use d07::{explain, poker::POKER};
+
+// cargo run --bin poker -- hands.txt [explain <hand> <hand> | table]
+fn main() {
+    let Some(path) = std::env::args().nth(1) else {
+        eprintln!("usage: poker <file>");
+        std::process::exit(1);
+    };
+    let input = std::fs::read_to_string(path).unwrap();
+    let args = std::env::args().skip(2).collect::<Vec<_>>();
+    if explain::cli(&POKER, input.trim_end(), &args) {
+        return;
+    }
+    println!("Winnings: {}", POKER.winnings(input.trim_end()));
+}
@@ -0,0 +1,249 @@
+use std::{cmp::Ordering, fmt};
+
+use crate::{Hand, Ruleset, TieBreak};
+
+// What settled the comparison between two hands
+#[derive(Debug, PartialEq, Eq)]
+pub enum Reason {
+    // the hands are different types
+    HandType,
+    // same type, so the tie break went through the cards until the two
+    // hands differed at `position` (from 0, in the order the tie break looks
+    // at them), where hand a had `a` and hand b had `b`
+    Card { position: usize, a: char, b: char },
+    // nothing separates them
+    Tie,
+}
+
+#[derive(Debug)]
+pub struct Explanation<'h, 'r> {
+    pub a: &'h Hand<'r>,
+    pub b: &'h Hand<'r>,
+    pub ordering: Ordering,
+    pub reason: Reason,
+}
+
+// why `a` sorts before, after or alongside `b`
+pub fn explain<'h, 'r>(a: &'h Hand<'r>, b: &'h Hand<'r>) -> Explanation<'h, 'r> {
+    let ruleset = a.ruleset;
+    let reason = if a.hand_type != b.hand_type {
+        Reason::HandType
+    } else {
+        let (sa, sb) = (
+            ruleset.tie_break_strengths(&a.cards),
+            ruleset.tie_break_strengths(&b.cards),
+        );
+        match sa.iter().zip(&sb).position(|(x, y)| x != y) {
+            Some(position) => Reason::Card {
+                position,
+                a: card(ruleset, sa[position]),
+                b: card(ruleset, sb[position]),
+            },
+            None => Reason::Tie,
+        }
+    };
+    Explanation {
+        a,
+        b,
+        ordering: a.cmp(b),
+        reason,
+    }
+}
+
+fn card(ruleset: &Ruleset, strength: usize) -> char {
+    ruleset.card_order.chars().nth(strength).unwrap()
+}
+
+// the hand, its type and what any wild cards played as
+fn describe(hand: &Hand) -> String {
+    if hand.is_substituted() {
+        format!(
+            "{}: {}, played as {}",
+            hand,
+            hand.hand_type,
+            hand.show(true)
+        )
+    } else {
+        format!("{}: {}", hand, hand.hand_type)
+    }
+}
+
+impl fmt::Display for Explanation<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", describe(self.a))?;
+        writeln!(f, "{}", describe(self.b))?;
+        let symbol = match self.ordering {
+            Ordering::Less => '<',
+            Ordering::Equal => '=',
+            Ordering::Greater => '>',
+        };
+        write!(f, "{} {} {}: ", self.a, symbol, self.b)?;
+        let (winner, loser) = match self.ordering {
+            Ordering::Less => (self.b, self.a),
+            _ => (self.a, self.b),
+        };
+        match self.reason {
+            Reason::HandType => write!(f, "{} beats {}", winner.hand_type, loser.hand_type),
+            Reason::Card { position, a, b } => {
+                let (win, lose) = if self.ordering == Ordering::Less {
+                    (b, a)
+                } else {
+                    (a, b)
+                };
+                let which = match self.a.ruleset.tie_break {
+                    TieBreak::Dealt => "card",
+                    TieBreak::Sorted | TieBreak::Grouped => "tie break card",
+                };
+                write!(
+                    f,
+                    "both {}, {} {} decides: {} beats {}",
+                    self.a.hand_type,
+                    which,
+                    position + 1,
+                    win,
+                    lose
+                )
+            }
+            Reason::Tie => write!(f, "both {}, nothing separates them", self.a.hand_type),
+        }
+    }
+}
+
+// every hand from weakest to strongest with what it wins
+pub fn table(hands: &[Hand]) -> String {
+    let width = hands
+        .iter()
+        .map(|h| h.show(false).len())
+        .max()
+        .unwrap_or(0)
+        .max("played".len());
+    let mut out = format!(
+        "{:>5}  {:width$}  {:15}  {:width$}  {:>6}  {:>10}\n",
+        "rank", "hand", "type", "played", "bid", "winnings"
+    );
+    let mut total = 0;
+    for (i, hand) in hands.iter().enumerate() {
+        let winnings = hand.bid * (i + 1);
+        total += winnings;
+        let played = if hand.is_substituted() {
+            hand.show(true)
+        } else {
+            String::new()
+        };
+        out += &format!(
+            "{:>5}  {:width$}  {:15}  {:width$}  {:>6}  {:>10}\n",
+            i + 1,
+            hand.to_string(),
+            hand.hand_type.to_string(),
+            played,
+            hand.bid,
+            winnings
+        );
+    }
+    out += &format!("total winnings: {}", total);
+    out
+}
+
+// `explain <hand> <hand>` or `table`, returning false for anything else
+pub fn cli(ruleset: &Ruleset, input: &str, args: &[String]) -> bool {
+    match args.first().map(String::as_str) {
+        Some("explain") if args.len() == 3 => {
+            // hands are given without bids
+            let hands = args[1..]
+                .iter()
+                .map(|hand| {
+                    Hand::parse(&format!("{} 0", hand), ruleset)
+                        .map(|(_, hand)| hand)
+                        .map_err(|e| format!("invalid hand {:?}: {}", hand, e))
+                })
+                .collect::<Result<Vec<_>, _>>();
+            match hands {
+                Ok(hands) => println!("{}", explain(&hands[0], &hands[1])),
+                Err(e) => eprintln!("{}", e),
+            }
+            true
+        }
+        Some("table") => {
+            println!("{}", table(&ruleset.ranked(input)));
+            true
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{poker::POKER, JOKERS, STANDARD};
+
+    const EXAMPLE: &str = r#"32T3K 765
+T55J5 684
+KK677 28
+KTJJT 220
+QQQJA 483"#;
+
+    fn hand<'r>(input: &str, ruleset: &'r Ruleset) -> Hand<'r> {
+        Hand::parse(&format!("{} 0", input), ruleset).unwrap().1
+    }
+
+    #[test]
+    fn test_explain_hand_type() {
+        let (a, b) = (hand("32T3K", &STANDARD), hand("KTJJT", &STANDARD));
+        let explanation = explain(&a, &b);
+        assert_eq!(explanation.ordering, Ordering::Less);
+        assert_eq!(explanation.reason, Reason::HandType);
+        assert_eq!(
+            explanation.to_string(),
+            "32T3K: pair
+KTJJT: two pair
+32T3K < KTJJT: two pair beats pair"
+        );
+    }
+
+    #[test]
+    fn test_explain_tie_break() {
+        let (a, b) = (hand("KK677", &STANDARD), hand("KTJJT", &STANDARD));
+        let explanation = explain(&a, &b);
+        assert_eq!(
+            explanation.reason,
+            Reason::Card {
+                position: 1,
+                a: 'K',
+                b: 'T'
+            }
+        );
+        assert!(explanation
+            .to_string()
+            .ends_with("KK677 > KTJJT: both two pair, card 2 decides: K beats T"));
+
+        // the jokers only count as 5 and Q for the type, the tie break still
+        // sees them as jokers
+        let (a, b) = (hand("T55J5", &JOKERS), hand("QQQJA", &JOKERS));
+        assert_eq!(
+            explain(&a, &b).to_string(),
+            "T55J5: 4 of a kind, played as T5555
+QQQJA: 4 of a kind, played as QQQQA
+T55J5 < QQQJA: both 4 of a kind, card 1 decides: Q beats T"
+        );
+
+        let a = hand("AS AD 4H 3C 2S", &POKER);
+        let b = hand("AH AC 5S 3D 2C", &POKER);
+        assert!(explain(&a, &b)
+            .to_string()
+            .ends_with("both pair, tie break card 3 decides: 5 beats 4"));
+        let b = hand("AH AC 4S 3D 2C", &POKER);
+        assert_eq!(explain(&a, &b).reason, Reason::Tie);
+    }
+
+    #[test]
+    fn test_table() {
+        let table = table(&JOKERS.ranked(EXAMPLE));
+        let lines = table.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 7);
+        assert_eq!(
+            lines[3],
+            "    3  T55J5   4 of a kind      T5555      684        2052"
+        );
+        assert_eq!(lines[6], "total winnings: 5905");
+    }
+}
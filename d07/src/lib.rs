@@ -0,0 +1,532 @@
+pub mod explain;
+pub mod poker;
+
+use std::{cmp::Ordering, collections::HashMap, fmt};
+
+use nom::{
+    character::complete::{self, line_ending, one_of, space1},
+    multi::{many1, separated_list1},
+    sequence::{pair, separated_pair},
+    IResult,
+};
+
+use poker::Category;
+
+// Everything that differs between the ways of playing Camel Cards (or poker)
+#[derive(Debug, PartialEq, Eq)]
+pub struct Ruleset {
+    // every card, weakest first
+    pub card_order: &'static str,
+    // cards that count as whatever makes the hand strongest
+    pub wild: &'static str,
+    // if not empty every card is written as its rank followed by one of these,
+    // with spaces between the cards
+    pub suits: &'static str,
+    pub scoring: Scoring,
+    pub tie_break: TieBreak,
+}
+
+// What a hand's type is made from
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Scoring {
+    // only how many of each card there are
+    Counts,
+    // the poker categories, with straights and flushes
+    Poker,
+}
+
+// How hands of the same type are told apart
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TieBreak {
+    // compare the first cards, then the second cards, ... as dealt
+    Dealt,
+    // compare the strongest cards, then the next strongest, ...
+    Sorted,
+    // compare the biggest group of cards, then the next biggest, ..., with
+    // the strongest first between groups of the same size. This is poker's
+    // kickers, so a straight only compares its top card.
+    Grouped,
+}
+
+// part 1
+pub const STANDARD: Ruleset = Ruleset {
+    card_order: "23456789TJQKA",
+    wild: "",
+    suits: "",
+    scoring: Scoring::Counts,
+    tie_break: TieBreak::Dealt,
+};
+
+// part 2: J is a joker, the weakest card on its own but wild when typing a hand
+pub const JOKERS: Ruleset = Ruleset {
+    card_order: "J23456789TQKA",
+    wild: "J",
+    suits: "",
+    scoring: Scoring::Counts,
+    tie_break: TieBreak::Dealt,
+};
+
+impl Ruleset {
+    pub fn strength(&self, card: char) -> usize {
+        self.card_order.find(card).unwrap()
+    }
+
+    pub fn is_wild(&self, card: char) -> bool {
+        self.wild.contains(card)
+    }
+
+    // the type of a hand without suits
+    pub fn hand_type(&self, cards: &[char]) -> HandType {
+        self.play(cards, &[]).0
+    }
+
+    // the type of a hand with no wild cards left in it
+    fn classify(&self, cards: &[char], suits: &[char]) -> HandType {
+        match self.scoring {
+            Scoring::Counts => HandType::of(cards),
+            Scoring::Poker => HandType::Poker(poker::category(self, cards, suits)),
+        }
+    }
+
+    // The strongest type the hand can make, along with the cards it's played
+    // as once every wild card has been swapped for a plain one. Every way of
+    // spending the wild cards on plain cards is tried, so the result is the
+    // best possible rather than a guess at it. A wild card keeps its suit.
+    pub fn play(&self, cards: &[char], suits: &[char]) -> (HandType, Vec<char>) {
+        let wilds = cards.iter().filter(|c| self.is_wild(**c)).count();
+        // strongest first, so that between equally good substitutions the
+        // stronger card is picked
+        let candidates = self
+            .card_order
+            .chars()
+            .rev()
+            .filter(|c| !self.is_wild(*c))
+            .collect::<Vec<_>>();
+        if wilds == 0 || candidates.is_empty() {
+            return (self.classify(cards, suits), cards.to_vec());
+        }
+        // put the substitutes where the wild cards were
+        let substitute = |extra: &[char]| {
+            let mut extra = extra.iter().rev().copied().collect::<Vec<_>>();
+            cards
+                .iter()
+                .map(|&c| {
+                    if self.is_wild(c) {
+                        extra.pop().unwrap()
+                    } else {
+                        c
+                    }
+                })
+                .collect::<Vec<_>>()
+        };
+        let mut best: Option<(HandType, Vec<char>)> = None;
+        substitutions(&candidates, wilds, &mut vec![], &mut |extra| {
+            let played = substitute(extra);
+            let hand_type = self.classify(&played, suits);
+            if best.as_ref().is_none_or(|(b, _)| hand_type > *b) {
+                best = Some((hand_type, played));
+            }
+        });
+        best.unwrap()
+    }
+
+    // compares two hands of the same type
+    fn tie_break(&self, a: &[char], b: &[char]) -> Ordering {
+        self.tie_break_strengths(a)
+            .cmp(&self.tie_break_strengths(b))
+    }
+
+    // card strengths in the order the tie break looks at them
+    pub(crate) fn tie_break_strengths(&self, cards: &[char]) -> Vec<usize> {
+        let mut strengths = cards.iter().map(|c| self.strength(*c)).collect::<Vec<_>>();
+        match self.tie_break {
+            TieBreak::Dealt => {}
+            TieBreak::Sorted => strengths.sort_unstable_by(|a, b| b.cmp(a)),
+            TieBreak::Grouped => {
+                if self.scoring == Scoring::Poker {
+                    if let Some(high) = poker::straight_high(self, cards) {
+                        return vec![high];
+                    }
+                }
+                let mut counts = HashMap::new();
+                for s in &strengths {
+                    *counts.entry(*s).or_insert(0) += 1;
+                }
+                strengths.sort_unstable_by(|a, b| (counts[b], b).cmp(&(counts[a], a)));
+            }
+        }
+        strengths
+    }
+
+    pub fn parse_hands<'a>(&self, input: &'a str) -> IResult<&'a str, Vec<Hand<'_>>> {
+        separated_list1(line_ending, |input| Hand::parse(input, self))(input)
+    }
+
+    // weakest first
+    pub fn ranked<'a>(&'a self, input: &str) -> Vec<Hand<'a>> {
+        let mut hands = self.parse_hands(input).unwrap().1;
+        hands.sort();
+        hands
+    }
+
+    // every hand wins its bid times its rank, from 1 for the weakest
+    pub fn winnings(&self, input: &str) -> usize {
+        self.ranked(input)
+            .iter()
+            .enumerate()
+            .fold(0, |acc, (i, hand)| acc + hand.bid * (i + 1))
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Hand<'r> {
+    pub cards: Vec<char>,
+    // empty unless the ruleset has suits
+    pub suits: Vec<char>,
+    pub bid: usize,
+    pub hand_type: HandType,
+    // the cards with every wild card swapped for what it plays as
+    pub played: Vec<char>,
+    pub ruleset: &'r Ruleset,
+}
+
+impl<'r> Hand<'r> {
+    pub fn parse<'a>(input: &'a str, ruleset: &'r Ruleset) -> IResult<&'a str, Hand<'r>> {
+        let (input, ((cards, suits), bid)) = if ruleset.suits.is_empty() {
+            separated_pair(many1(one_of(ruleset.card_order)), space1, complete::u32)(input)
+                .map(|(input, (cards, bid))| (input, ((cards, vec![]), bid)))?
+        } else {
+            separated_pair(
+                separated_list1(
+                    space1,
+                    pair(one_of(ruleset.card_order), one_of(ruleset.suits)),
+                ),
+                space1,
+                complete::u32,
+            )(input)
+            .map(|(input, (cards, bid))| (input, (cards.into_iter().unzip(), bid)))?
+        };
+        let (hand_type, played) = ruleset.play(&cards, &suits);
+        Ok((
+            input,
+            Hand {
+                cards,
+                suits,
+                bid: bid as usize,
+                hand_type,
+                played,
+                ruleset,
+            },
+        ))
+    }
+
+    pub fn is_substituted(&self) -> bool {
+        self.played != self.cards
+    }
+
+    // the cards as dealt, or as played if `played` is set
+    pub fn show(&self, played: bool) -> String {
+        let cards = if played { &self.played } else { &self.cards };
+        if self.suits.is_empty() {
+            cards.iter().collect()
+        } else {
+            cards
+                .iter()
+                .zip(&self.suits)
+                .map(|(c, s)| format!("{}{}", c, s))
+                .collect::<Vec<_>>()
+                .join(" ")
+        }
+    }
+}
+
+impl fmt::Display for Hand<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.show(false))
+    }
+}
+
+// only hands dealt under the same ruleset can be compared
+impl Ord for Hand<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.hand_type
+            .cmp(&other.hand_type)
+            .then_with(|| self.ruleset.tie_break(&self.cards, &other.cards))
+    }
+}
+
+impl PartialOrd for Hand<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// Every multiset of `wilds` cards drawn from `candidates`, each passed to
+// `visit` in turn
+fn substitutions(
+    candidates: &[char],
+    wilds: usize,
+    chosen: &mut Vec<char>,
+    visit: &mut impl FnMut(&[char]),
+) {
+    match candidates.split_first() {
+        _ if wilds == 0 => visit(chosen),
+        None => {}
+        Some((&card, rest)) => {
+            // take wilds..=0 copies of this card and share the rest out
+            // among the remaining candidates
+            for copies in (0..=wilds).rev() {
+                let len = chosen.len();
+                chosen.extend(std::iter::repeat_n(card, copies));
+                substitutions(rest, wilds - copies, chosen, visit);
+                chosen.truncate(len);
+            }
+        }
+    }
+}
+
+// How good a hand is before any tie break
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
+pub enum HandType {
+    // How many of each card a hand holds, most first. Comparing these
+    // lexicographically gives the usual order for five cards
+    //   [5] > [4, 1] > [3, 2] > [3, 1, 1] > [2, 2, 1] > [2, 1, 1, 1] > [1, 1, 1, 1, 1]
+    // and carries on working for hands of any other size.
+    Counts(Vec<usize>),
+    Poker(Category),
+}
+
+impl HandType {
+    pub fn of(cards: &[char]) -> HandType {
+        HandType::Counts(counts(cards))
+    }
+}
+
+// how many of each card there are, most first
+pub(crate) fn counts(cards: &[char]) -> Vec<usize> {
+    let mut cards_map = HashMap::new();
+    for c in cards {
+        *cards_map.entry(*c).or_insert(0) += 1;
+    }
+    let mut counts = cards_map.into_values().collect::<Vec<usize>>();
+    counts.sort_unstable_by(|a, b| b.cmp(a));
+    counts
+}
+
+impl fmt::Display for HandType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let counts = match self {
+            HandType::Counts(counts) => counts,
+            HandType::Poker(category) => return write!(f, "{}", category),
+        };
+        let name = match counts[..] {
+            [] => "no cards".to_string(),
+            [1, ..] => "high card".to_string(),
+            [2] | [2, 1, ..] => "pair".to_string(),
+            [2, 2] | [2, 2, 1, ..] => "two pair".to_string(),
+            [3, 2] | [3, 2, 1, ..] => "full house".to_string(),
+            [n] | [n, 1, ..] => format!("{} of a kind", n),
+            ref counts => format!("{:?}", counts),
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = r#"32T3K 765
+T55J5 684
+KK677 28
+KTJJT 220
+QQQJA 483"#;
+
+    const EXAMPLE2: &str = r#"32T3K 765
+T55J5 684
+KK677 28
+KTJJT 220
+QQQJA 483
+QQQQA 483
+QQQQQ 483"#;
+
+    fn hand_types(ruleset: &Ruleset) -> Vec<String> {
+        ruleset
+            .parse_hands(EXAMPLE2)
+            .unwrap()
+            .1
+            .iter()
+            .map(|h| h.hand_type.to_string())
+            .collect()
+    }
+
+    fn sorted(ruleset: &Ruleset) -> Vec<&str> {
+        let mut hands = ruleset.parse_hands(EXAMPLE2).unwrap().1;
+        hands.sort();
+        hands
+            .iter()
+            .map(|h| {
+                let cards = h.cards.iter().collect::<String>();
+                EXAMPLE2.lines().find(|l| l.starts_with(&cards)).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_winnings() {
+        assert_eq!(STANDARD.winnings(EXAMPLE), 6440);
+        assert_eq!(JOKERS.winnings(EXAMPLE), 5905);
+    }
+
+    #[test]
+    fn test_parse_hands() {
+        let hands = STANDARD.parse_hands(EXAMPLE2).unwrap();
+        assert_eq!(hands.0, "");
+        assert_eq!(hands.1[0].cards, vec!['3', '2', 'T', '3', 'K']);
+        assert_eq!(hands.1[0].bid, 765);
+        assert!(Hand::parse("32X3K 765", &STANDARD).is_err());
+    }
+
+    #[test]
+    fn test_hand_types() {
+        assert_eq!(
+            hand_types(&STANDARD),
+            vec![
+                "pair",
+                "3 of a kind",
+                "two pair",
+                "two pair",
+                "3 of a kind",
+                "4 of a kind",
+                "5 of a kind"
+            ]
+        );
+        assert_eq!(
+            hand_types(&JOKERS),
+            vec![
+                "pair",
+                "4 of a kind",
+                "two pair",
+                "4 of a kind",
+                "4 of a kind",
+                "4 of a kind",
+                "5 of a kind"
+            ]
+        );
+        assert_eq!(JOKERS.hand_type(&['J'; 5]), HandType::Counts(vec![5]));
+    }
+
+    #[test]
+    fn test_hand_type_order() {
+        let order = [
+            "23456", "22345", "22335", "22235", "22233", "22223", "22222",
+        ]
+        .map(|cards| HandType::of(&cards.chars().collect::<Vec<_>>()));
+        assert!(order.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn test_any_hand_size() {
+        let hands = STANDARD.parse_hands("AAKKQQ 1\nAAAKK 2\n2 3").unwrap().1;
+        assert_eq!(hands[0].hand_type, HandType::Counts(vec![2, 2, 2]));
+        assert_eq!(hands[0].hand_type.to_string(), "[2, 2, 2]");
+        assert_eq!(hands[1].hand_type.to_string(), "full house");
+        assert_eq!(hands[2].hand_type.to_string(), "high card");
+        assert_eq!(hands[2].bid, 3);
+        let seven = STANDARD.hand_type(&"7772234".chars().collect::<Vec<_>>());
+        assert_eq!(seven.to_string(), "full house");
+    }
+
+    #[test]
+    fn test_multiple_wild_cards() {
+        let ruleset = Ruleset {
+            card_order: "*J23456789TQKA",
+            wild: "*J",
+            ..JOKERS
+        };
+        let (hand_type, played) = ruleset.play(&"K*2J2".chars().collect::<Vec<_>>(), &[]);
+        assert_eq!(hand_type.to_string(), "4 of a kind");
+        assert_eq!(played, vec!['K', '2', '2', '2', '2']);
+        // nothing but wild cards plays as the strongest cards
+        assert_eq!(
+            ruleset.play(&"J**J".chars().collect::<Vec<_>>(), &[]),
+            (HandType::Counts(vec![4]), vec!['A'; 4])
+        );
+    }
+
+    #[test]
+    fn test_search_matches_heuristic() {
+        // for a single joker rank the old "add the jokers to the biggest
+        // count" rule is optimal, so the search has to agree with it
+        let cards = ['J', '2', '3', '4', '5'];
+        for i in 0..5usize.pow(5) {
+            let hand = (0..5)
+                .map(|p| cards[i / 5usize.pow(p) % 5])
+                .collect::<Vec<_>>();
+            let jokers = hand.iter().filter(|&&c| c == 'J').count();
+            let mut counts = counts(
+                &hand
+                    .iter()
+                    .copied()
+                    .filter(|&c| c != 'J')
+                    .collect::<Vec<_>>(),
+            );
+            match counts.first_mut() {
+                Some(highest) => *highest += jokers,
+                None => counts.push(jokers),
+            }
+            assert_eq!(
+                JOKERS.hand_type(&hand),
+                HandType::Counts(counts),
+                "{:?}",
+                hand
+            );
+        }
+    }
+
+    #[test]
+    fn test_sort_hands() {
+        assert_eq!(
+            sorted(&STANDARD),
+            vec![
+                "32T3K 765",
+                "KTJJT 220",
+                "KK677 28",
+                "T55J5 684",
+                "QQQJA 483",
+                "QQQQA 483",
+                "QQQQQ 483",
+            ]
+        );
+        // KTJJT is four of a kind with jokers and beats QQQJA and QQQQA on
+        // its first card
+        assert_eq!(
+            sorted(&JOKERS),
+            vec![
+                "32T3K 765",
+                "KK677 28",
+                "T55J5 684",
+                "QQQJA 483",
+                "QQQQA 483",
+                "KTJJT 220",
+                "QQQQQ 483",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sorted_tie_break() {
+        let ruleset = Ruleset {
+            tie_break: TieBreak::Sorted,
+            ..STANDARD
+        };
+        let a = Hand::parse("2AKQ9 1", &ruleset).unwrap().1;
+        let b = Hand::parse("8AKQ3 1", &ruleset).unwrap().1;
+        // dealt order puts 2 against 8, sorted order puts A, K, Q against
+        // A, K, Q and then 9 against 8
+        assert_eq!(STANDARD.tie_break(&a.cards, &b.cards), Ordering::Less);
+        assert!(a > b);
+        let c = Hand::parse("9AKQ2 1", &ruleset).unwrap().1;
+        assert_eq!(a.cmp(&c), Ordering::Equal);
+    }
+}
@@ -0,0 +1,151 @@
+use std::fmt;
+
+use crate::{counts, Ruleset, Scoring, TieBreak};
+
+// Hands are written `AS KD TH 9C 2S 765`: rank then suit for each card,
+// then the bid
+pub const POKER: Ruleset = Ruleset {
+    card_order: "23456789TJQKA",
+    wild: "",
+    suits: "CDHS",
+    scoring: Scoring::Poker,
+    tie_break: TieBreak::Grouped,
+};
+
+// weakest first
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub enum Category {
+    HighCard,
+    Pair,
+    TwoPair,
+    ThreeOfAKind,
+    Straight,
+    Flush,
+    FullHouse,
+    FourOfAKind,
+    StraightFlush,
+    // only possible with wild cards
+    FiveOfAKind,
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Category::HighCard => "high card",
+            Category::Pair => "pair",
+            Category::TwoPair => "two pair",
+            Category::ThreeOfAKind => "three of a kind",
+            Category::Straight => "straight",
+            Category::Flush => "flush",
+            Category::FullHouse => "full house",
+            Category::FourOfAKind => "four of a kind",
+            Category::StraightFlush => "straight flush",
+            Category::FiveOfAKind => "five of a kind",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// Straights and flushes take the whole hand, so they need at least five cards
+pub fn category(ruleset: &Ruleset, cards: &[char], suits: &[char]) -> Category {
+    let flush =
+        cards.len() >= 5 && suits.len() == cards.len() && suits.iter().all(|s| *s == suits[0]);
+    let straight = straight_high(ruleset, cards).is_some();
+    match counts(cards)[..] {
+        [n, ..] if n >= 5 => Category::FiveOfAKind,
+        _ if straight && flush => Category::StraightFlush,
+        [4, ..] => Category::FourOfAKind,
+        [3, 2, ..] => Category::FullHouse,
+        _ if flush => Category::Flush,
+        _ if straight => Category::Straight,
+        [3, ..] => Category::ThreeOfAKind,
+        [2, 2, ..] => Category::TwoPair,
+        [2, ..] => Category::Pair,
+        _ => Category::HighCard,
+    }
+}
+
+// The strength of the top card if the hand is a straight. The strongest card
+// can also play low, below the weakest, so A 2 3 4 5 is a straight to the 5.
+pub fn straight_high(ruleset: &Ruleset, cards: &[char]) -> Option<usize> {
+    if cards.len() < 5 {
+        return None;
+    }
+    let mut strengths = cards
+        .iter()
+        .map(|c| ruleset.strength(*c))
+        .collect::<Vec<_>>();
+    strengths.sort_unstable();
+    let run = |s: &[usize]| s.windows(2).all(|w| w[1] == w[0] + 1);
+    let top = ruleset.card_order.chars().count() - 1;
+    let n = strengths.len();
+    if run(&strengths) {
+        Some(strengths[n - 1])
+    } else if strengths[n - 1] == top && strengths[0] == 0 && run(&strengths[..n - 1]) {
+        Some(strengths[n - 2])
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Hand, HandType};
+
+    fn hand(input: &str) -> Hand<'static> {
+        Hand::parse(input, &POKER).unwrap().1
+    }
+
+    #[test]
+    fn test_categories() {
+        let category = |input: &str| match hand(input).hand_type {
+            HandType::Poker(category) => category,
+            hand_type => panic!("{:?} isn't a poker hand", hand_type),
+        };
+        assert_eq!(category("AS KD TH 9C 2S 1"), Category::HighCard);
+        assert_eq!(category("AS AD TH 9C 2S 1"), Category::Pair);
+        assert_eq!(category("AS AD TH TC 2S 1"), Category::TwoPair);
+        assert_eq!(category("AS AD AH 9C 2S 1"), Category::ThreeOfAKind);
+        assert_eq!(category("6S 5D 4H 3C 2S 1"), Category::Straight);
+        assert_eq!(category("AS 5D 4H 3C 2S 1"), Category::Straight);
+        assert_eq!(category("AS KS QH JC TS 1"), Category::Straight);
+        assert_eq!(category("KS AD 2H 3C 4S 1"), Category::HighCard);
+        assert_eq!(category("AS KS TS 9S 2S 1"), Category::Flush);
+        assert_eq!(category("AS AD AH 9C 9S 1"), Category::FullHouse);
+        assert_eq!(category("AS AD AH AC 9S 1"), Category::FourOfAKind);
+        assert_eq!(category("9H TH JH QH KH 1"), Category::StraightFlush);
+    }
+
+    #[test]
+    fn test_kickers() {
+        // a pair of 3s beats a pair of 2s whatever the kickers
+        assert!(hand("3S 3D 4H 5C 6S 1") > hand("2S 2D AH KC QS 1"));
+        // same pair, so the kickers decide from the top down
+        assert!(hand("2S 2D AH KC 4S 1") > hand("2H 2C AD KH 3S 1"));
+        // two pair: top pair, then bottom pair, then the kicker
+        assert!(hand("KS KD 3H 3C 2S 1") > hand("QS QD JH JC AS 1"));
+        assert!(hand("KS KD 4H 4C 2S 1") > hand("KH KC 3S 3D AS 1"));
+        assert!(hand("KS KD 4H 4C 6S 1") > hand("KH KC 4S 4D 5S 1"));
+        // full houses compare the three first
+        assert!(hand("3S 3D 3H 2C 2S 1") > hand("2H 2C 2S AH AD 1"));
+        // the wheel is the lowest straight
+        assert!(hand("6S 5D 4H 3C 2S 1") > hand("AS 5D 4H 3C 2S 1"));
+        // the same ranks in a different order are a tie
+        assert_eq!(
+            hand("AS KD TH 9C 2S 1").cmp(&hand("2C 9D TS KH AC 1")),
+            std::cmp::Ordering::Equal
+        );
+    }
+
+    #[test]
+    fn test_winnings() {
+        let input = "2S 3S 4S 5S 6S 10
+AS AD KH KC 2S 20
+AH AC KS KD 3S 30
+7H 8D 9S TC JH 40";
+        // ranked: two pair with a 2, two pair with a 3, straight, straight flush
+        assert_eq!(POKER.winnings(input), 20 + 30 * 2 + 40 * 3 + 10 * 4);
+        assert!(Hand::parse("AS KD T 9C 2S 1", &POKER).is_err());
+    }
+}
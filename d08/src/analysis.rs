@@ -0,0 +1,244 @@
+use std::fmt;
+
+use crate::network::{Instruction, Network};
+
+// What a ghost on one start node does
+#[derive(Debug, PartialEq)]
+pub enum Outcome {
+    // first at a goal after this many steps
+    Reaches(usize),
+    // loops forever without ever being at a goal
+    Never,
+    // hits an instruction it can't follow before ever being at a goal
+    Stuck(String),
+}
+
+// Everything worth knowing about a network before walking it
+#[derive(Debug, PartialEq)]
+pub struct Report<'a> {
+    pub nodes: usize,
+    pub goals: Vec<&'a str>,
+    // no start node can get to these whatever the instructions
+    pub unreachable: Vec<&'a str>,
+    // nodes other than goals that only lead back to themselves, or nowhere
+    pub dead_ends: Vec<&'a str>,
+    // strongly connected components, biggest first
+    pub components: Vec<Vec<&'a str>>,
+    pub starts: Vec<(&'a str, Outcome)>,
+}
+
+pub fn analyse<'a>(
+    network: &Network<'a>,
+    starts: &[usize],
+    goals: &[usize],
+    instructions: &[Instruction],
+) -> Report<'a> {
+    let names = |ids: Vec<usize>| ids.into_iter().map(|id| network.name(id)).collect();
+    let is_goal = network.goal_mask(goals);
+
+    // plain graph search, ignoring the instructions
+    let mut reachable = vec![false; network.len()];
+    let mut stack = starts.to_vec();
+    while let Some(node) = stack.pop() {
+        if !reachable[node] {
+            reachable[node] = true;
+            stack.extend(network.successors(node));
+        }
+    }
+
+    let dead_ends = (0..network.len())
+        .filter(|&id| !is_goal[id] && network.successors(id).iter().all(|&next| next == id))
+        .collect();
+
+    let mut components = strongly_connected_components(network)
+        .into_iter()
+        .map(names)
+        .collect::<Vec<Vec<_>>>();
+    components.sort_by_key(|c| std::cmp::Reverse(c.len()));
+
+    let starts = starts
+        .iter()
+        .map(|&start| {
+            let cycle = network.cycle(start, &is_goal, instructions);
+            let outcome = match (cycle.hits.first(), cycle.stuck) {
+                (Some(&steps), _) => Outcome::Reaches(steps),
+                (None, Some(stuck)) => Outcome::Stuck(stuck.reason),
+                (None, None) => Outcome::Never,
+            };
+            (network.name(start), outcome)
+        })
+        .collect();
+
+    Report {
+        nodes: network.len(),
+        goals: names(goals.to_vec()),
+        unreachable: names((0..network.len()).filter(|&id| !reachable[id]).collect()),
+        dead_ends: names(dead_ends),
+        components,
+        starts,
+    }
+}
+
+// Tarjan's algorithm: a depth first search where every node gets the
+// smallest index it can get back to, and a node that can't get back above
+// itself is the root of a component made of everything still on the stack
+// above it
+fn strongly_connected_components(network: &Network) -> Vec<Vec<usize>> {
+    struct Tarjan<'n, 'a> {
+        network: &'n Network<'a>,
+        index: Vec<Option<usize>>,
+        low: Vec<usize>,
+        on_stack: Vec<bool>,
+        stack: Vec<usize>,
+        next_index: usize,
+        components: Vec<Vec<usize>>,
+    }
+
+    impl Tarjan<'_, '_> {
+        fn visit(&mut self, node: usize) {
+            self.index[node] = Some(self.next_index);
+            self.low[node] = self.next_index;
+            self.next_index += 1;
+            self.stack.push(node);
+            self.on_stack[node] = true;
+            for &next in self.network.successors(node) {
+                match self.index[next] {
+                    None => {
+                        self.visit(next);
+                        self.low[node] = self.low[node].min(self.low[next]);
+                    }
+                    Some(index) if self.on_stack[next] => {
+                        self.low[node] = self.low[node].min(index)
+                    }
+                    Some(_) => {}
+                }
+            }
+            if Some(self.low[node]) == self.index[node] {
+                let mut component = vec![];
+                loop {
+                    let member = self.stack.pop().unwrap();
+                    self.on_stack[member] = false;
+                    component.push(member);
+                    if member == node {
+                        break;
+                    }
+                }
+                component.sort();
+                self.components.push(component);
+            }
+        }
+    }
+
+    let mut tarjan = Tarjan {
+        network,
+        index: vec![None; network.len()],
+        low: vec![0; network.len()],
+        on_stack: vec![false; network.len()],
+        stack: vec![],
+        next_index: 0,
+        components: vec![],
+    };
+    for node in 0..network.len() {
+        if tarjan.index[node].is_none() {
+            tarjan.visit(node);
+        }
+    }
+    tarjan.components
+}
+
+impl fmt::Display for Report<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let list = |names: &[&str]| {
+            if names.is_empty() {
+                "none".to_string()
+            } else {
+                names.join(", ")
+            }
+        };
+        writeln!(f, "{} nodes, goals: {}", self.nodes, list(&self.goals))?;
+        writeln!(
+            f,
+            "unreachable from the starts: {}",
+            list(&self.unreachable)
+        )?;
+        writeln!(f, "dead ends: {}", list(&self.dead_ends))?;
+        let (loops, singles): (Vec<_>, Vec<_>) = self.components.iter().partition(|c| c.len() > 1);
+        writeln!(
+            f,
+            "strongly connected components: {} with more than one node, {} single nodes",
+            loops.len(),
+            singles.len()
+        )?;
+        for component in loops {
+            writeln!(f, "  {}", list(component))?;
+        }
+        for (start, outcome) in &self.starts {
+            match outcome {
+                Outcome::Reaches(steps) => {
+                    writeln!(f, "{}: first at a goal after {} steps", start, steps)?
+                }
+                Outcome::Never => writeln!(f, "{}: never reaches a goal", start)?,
+                Outcome::Stuck(e) => writeln!(f, "{}: stuck, {}", start, e)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{compile, tests::EXAMPLE1, tests::EXAMPLE3};
+
+    fn report(input: &str, start: &str, goal: &str) -> String {
+        let (network, instructions) = compile(input).unwrap();
+        let starts = network.select(|n| n.ends_with(start));
+        let goals = network.select(|n| n.ends_with(goal));
+        analyse(&network, &starts, &goals, &instructions).to_string()
+    }
+
+    #[test]
+    fn test_example1() {
+        assert_eq!(
+            report(EXAMPLE1, "AAA", "ZZZ"),
+            "7 nodes, goals: ZZZ
+unreachable from the starts: none
+dead ends: DDD, EEE, GGG
+strongly connected components: 0 with more than one node, 7 single nodes
+AAA: first at a goal after 2 steps
+"
+        );
+        // starting at BBB only the dead ends are in reach
+        let report = report(EXAMPLE1, "BBB", "ZZZ");
+        assert!(report.contains("unreachable from the starts: AAA, CCC, GGG, ZZZ\n"));
+        assert!(report.contains("BBB: never reaches a goal\n"));
+    }
+
+    #[test]
+    fn test_example3() {
+        assert_eq!(
+            report(EXAMPLE3, "A", "Z"),
+            "8 nodes, goals: 11Z, 22Z
+unreachable from the starts: none
+dead ends: XXX
+strongly connected components: 2 with more than one node, 3 single nodes
+  22B, 22C, 22Z
+  11B, 11Z
+11A: first at a goal after 2 steps
+22A: first at a goal after 3 steps
+"
+        );
+        // starting with R sends both ghosts straight to XXX
+        let report = report(&EXAMPLE3.replacen("LR", "RL", 1), "A", "Z");
+        assert!(report.contains("11A: never reaches a goal\n22A: never reaches a goal\n"));
+    }
+
+    #[test]
+    fn test_stuck() {
+        let stuck = report("2\n\nAAA = (ZZZ)\nZZZ = (ZZZ)", "A", "Z");
+        assert!(stuck.contains("AAA: stuck, AAA has no successor 2 (1 successors)\n"));
+        // getting stuck after reaching a goal doesn't matter
+        let reached = report("LR\n\nAAA = (ZZZ, AAA)\nZZZ = (ZZZ)", "A", "Z");
+        assert!(reached.contains("AAA: first at a goal after 1 steps\n"));
+    }
+}
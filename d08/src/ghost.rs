@@ -0,0 +1,295 @@
+use std::{collections::HashMap, hash::Hash};
+
+use num::Integer;
+
+// Where one ghost is at a goal. A ghost's state is its node together with
+// where it is in the instructions, so once a state comes round again the walk
+// repeats forever. The first `offset` steps happen once and everything from
+// there on loops every `period` steps.
+//
+// A ghost that gets stuck never loops: its walk just ends, and it's only ever
+// at a goal on the steps in `hits`.
+#[derive(Debug, PartialEq)]
+pub struct Cycle {
+    pub offset: usize,
+    pub period: usize,
+    // every step before offset + period that ends on a goal, in order. The
+    // ones from offset on come round again every period steps.
+    pub hits: Vec<usize>,
+    pub stuck: Option<Stuck>,
+}
+
+// The last step a ghost gets to, and why it can't take the next one
+#[derive(Debug, PartialEq, Clone)]
+pub struct Stuck {
+    pub at: usize,
+    pub reason: String,
+}
+
+impl Cycle {
+    // `next(node, i)` is where instruction `i` takes `node`, or why it can't
+    pub fn find<N: Copy + Eq + Hash>(
+        start: N,
+        instructions: usize,
+        next: impl Fn(N, usize) -> Result<N, String>,
+        is_goal: impl Fn(N) -> bool,
+    ) -> Cycle {
+        let mut seen = HashMap::new();
+        let mut hits = vec![];
+        let mut node = start;
+        let mut step = 0;
+        loop {
+            if let Some(&offset) = seen.get(&(node, step % instructions)) {
+                return Cycle {
+                    offset,
+                    period: step - offset,
+                    hits,
+                    stuck: None,
+                };
+            }
+            seen.insert((node, step % instructions), step);
+            if is_goal(node) {
+                hits.push(step);
+            }
+            node = match next(node, step % instructions) {
+                Ok(node) => node,
+                Err(reason) => {
+                    return Cycle {
+                        offset: step + 1,
+                        period: 0,
+                        hits,
+                        stuck: Some(Stuck { at: step, reason }),
+                    }
+                }
+            };
+            step += 1;
+        }
+    }
+
+    pub fn is_hit(&self, step: usize) -> bool {
+        if step < self.offset || self.stuck.is_some() {
+            self.hits.contains(&step)
+        } else {
+            let step = self.offset + (step - self.offset) % self.period;
+            self.hits.contains(&step)
+        }
+    }
+
+    // the hits that come round again, as residues mod the period
+    fn residues(&self) -> Vec<u128> {
+        self.hits
+            .iter()
+            .filter(|&&h| h >= self.offset)
+            .map(|&h| (h % self.period) as u128)
+            .collect()
+    }
+}
+
+// The first step where every ghost is at a goal at once.
+//
+// Before the last ghost settles into its loop the answer has to be one of that
+// ghost's one-off hits, so those are checked directly. After that each ghost
+// is at a goal exactly when the step is one of its residues mod its period,
+// and the generalized Chinese Remainder Theorem combines those into residues
+// mod the lcm of all the periods.
+//
+// Once a ghost is stuck the walk is over, so if any are the answer can only be
+// one of the hits of the first to get stuck.
+pub fn solve(cycles: &[Cycle]) -> Result<u128, String> {
+    if let Some((first, stuck)) = cycles
+        .iter()
+        .filter_map(|c| c.stuck.as_ref().map(|stuck| (c, stuck)))
+        .min_by_key(|(_, stuck)| stuck.at)
+    {
+        return first
+            .hits
+            .iter()
+            .find(|&&h| cycles.iter().all(|c| c.is_hit(h)))
+            .map(|&step| step as u128)
+            .ok_or_else(|| stuck.reason.clone());
+    }
+    let Some(last) = cycles.iter().max_by_key(|c| c.offset) else {
+        return Err("there are no ghosts".to_string());
+    };
+    if let Some(&step) = last
+        .hits
+        .iter()
+        .filter(|&&h| h < last.offset)
+        .find(|&&h| cycles.iter().all(|c| c.is_hit(h)))
+    {
+        return Ok(step as u128);
+    }
+
+    let mut combined = vec![(0, 1)];
+    for cycle in cycles {
+        let mut next = vec![];
+        for &(a, m) in &combined {
+            for r in cycle.residues() {
+                next.extend(crt(a, m, r, cycle.period as u128)?);
+            }
+        }
+        combined = next;
+        combined.sort();
+        combined.dedup();
+    }
+    // the smallest step at or after the last offset for each residue, which
+    // is less than the offset plus the period so can't overflow
+    let start = last.offset as u128;
+    combined
+        .iter()
+        .map(|&(a, m)| {
+            if a >= start {
+                a
+            } else {
+                a + (start - a).div_ceil(m) * m
+            }
+        })
+        .min()
+        .ok_or_else(|| "the ghosts are never all at a goal on the same step".to_string())
+}
+
+// x = a mod m and x = b mod n, as x mod lcm(m, n), if there's a solution.
+// m is an lcm this has already returned, so it fits in an i128.
+fn crt(a: u128, m: u128, b: u128, n: u128) -> Result<Option<(u128, u128)>, String> {
+    let (a, m, b, n) = (a as i128, m as i128, b as i128, n as i128);
+    let e = m.extended_gcd(&n);
+    let g = e.gcd;
+    if (b - a) % g != 0 {
+        return Ok(None);
+    }
+    let overflow = || "the combined period overflows".to_string();
+    let lcm = (m / g).checked_mul(n).ok_or_else(overflow)?;
+    // m * x = g mod n, so stepping a by m * x * (b - a) / g lands on b mod n
+    let k = ((b - a) / g % (n / g))
+        .checked_mul(e.x % (n / g))
+        .ok_or_else(overflow)?
+        % (n / g);
+    let x = m
+        .checked_mul(k)
+        .and_then(|step| a.checked_add(step))
+        .ok_or_else(overflow)?;
+    Ok(Some((x.rem_euclid(lcm) as u128, lcm as u128)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a ghost that just follows a list of successors, one instruction long
+    fn cycle(next: &[usize], goals: &[usize]) -> Cycle {
+        Cycle::find(0, 1, |node, _| Ok(next[node]), |node| goals.contains(&node))
+    }
+
+    #[test]
+    fn test_find_cycle() {
+        // 0 -> 1 -> 2 -> 3 -> 1 with 2 the goal
+        assert_eq!(
+            cycle(&[1, 2, 3, 1], &[2]),
+            Cycle {
+                offset: 1,
+                period: 3,
+                hits: vec![2],
+                stuck: None
+            }
+        );
+        let c = cycle(&[1, 2, 3, 1], &[2]);
+        assert!((0..20).all(|s| c.is_hit(s) == (s % 3 == 2)));
+    }
+
+    #[test]
+    fn test_crt() {
+        assert_eq!(crt(2, 3, 3, 5), Ok(Some((8, 15))));
+        assert_eq!(crt(1, 4, 3, 6), Ok(Some((9, 12))));
+        assert_eq!(crt(0, 4, 1, 6), Ok(None));
+    }
+
+    #[test]
+    fn test_combined_period_overflows() {
+        // pairwise coprime periods of about 2^63, hitting a goal at 1 mod each
+        let looping = |period: usize| Cycle {
+            offset: 0,
+            period,
+            hits: vec![1],
+            stuck: None,
+        };
+        let periods = [(1 << 63) - 1, 1 << 63, (1 << 63) + 1];
+        // two of them still fit
+        assert_eq!(solve(&[looping(periods[0]), looping(periods[1])]), Ok(1));
+        assert_eq!(
+            solve(&periods.map(looping)),
+            Err("the combined period overflows".to_string())
+        );
+    }
+
+    #[test]
+    fn test_solve() {
+        // hits at 2, 5, 8, ... and at every odd step: lcm of the first hits
+        // would say 2
+        let a = cycle(&[1, 2, 3, 1], &[2]);
+        let b = cycle(&[1, 0], &[1]);
+        assert_eq!(solve(&[a, b]), Ok(5));
+
+        // two hits per loop, at 1 and 3 mod 5, against 2 mod 3
+        let a = cycle(&[1, 2, 3, 4, 0], &[1, 3]);
+        let b = cycle(&[1, 2, 0], &[2]);
+        assert_eq!(solve(&[a, b]), Ok(8));
+
+        // a one-off hit before the ghost gets stuck
+        let a = cycle(&[1, 2, 2], &[1]);
+        let b = cycle(&[1, 1], &[1]);
+        assert_eq!(solve(&[a, b]), Ok(1));
+    }
+
+    // like `cycle`, but the ghost can't leave `dead_end`
+    fn stuck(next: &[usize], goals: &[usize], dead_end: usize) -> Cycle {
+        Cycle::find(
+            0,
+            1,
+            |node, _| {
+                if node == dead_end {
+                    Err(format!("stuck on {}", node))
+                } else {
+                    Ok(next[node])
+                }
+            },
+            |node| goals.contains(&node),
+        )
+    }
+
+    #[test]
+    fn test_stuck() {
+        // 0 -> 1 -> 2 and no further
+        let a = stuck(&[1, 2, 3], &[1, 2], 2);
+        assert_eq!(
+            a.stuck,
+            Some(Stuck {
+                at: 2,
+                reason: "stuck on 2".to_string()
+            })
+        );
+        assert_eq!(a.hits, [1, 2]);
+        assert!(!a.is_hit(3) && !a.is_hit(5));
+
+        // at a goal on 1 and 2, then stuck, against every other step
+        let b = cycle(&[1, 0], &[0]);
+        assert_eq!(solve(&[a, b]), Ok(2));
+        let a = stuck(&[1, 2, 3], &[1, 2], 2);
+        let b = cycle(&[1, 2, 0], &[0]);
+        assert_eq!(solve(&[a, b]), Err("stuck on 2".to_string()));
+        // only the first ghost to get stuck limits the walk
+        let a = stuck(&[1, 2, 3, 4], &[3], 3);
+        let b = stuck(&[1, 2, 3, 4], &[0, 1, 2, 3], 4);
+        assert_eq!(solve(&[a, b]), Ok(3));
+    }
+
+    #[test]
+    fn test_no_common_step() {
+        // even steps from 2 on against odd steps
+        let a = cycle(&[1, 2, 1], &[2]);
+        let b = cycle(&[1, 0], &[1]);
+        assert!(solve(&[a, b]).is_err());
+        // never at a goal at all
+        let c = cycle(&[1, 1], &[]);
+        assert!(solve(&[cycle(&[1, 0], &[1]), c]).is_err());
+    }
+}
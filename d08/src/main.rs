@@ -1,24 +1,66 @@
+mod analysis;
+mod ghost;
+mod network;
+
 use std::collections::HashMap;
 
 use nom::{
     bytes::complete::tag,
-    character::complete::{alpha1, alphanumeric1, line_ending},
-    multi::separated_list1,
+    character::complete::{alphanumeric1, line_ending},
+    multi::{separated_list0, separated_list1},
     sequence::{preceded, separated_pair, terminated},
     IResult,
 };
+use regex::Regex;
+
+use network::{compile_instructions, Instruction, Network};
 
 fn main() {
     let input = include_str!("../input.txt");
-    println!("Part 1: {}", part1(input));
-    println!("Part 2: {}", part2(input));
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    // cargo run -- walk|analyse <start regex> <goal regex>
+    if let [command, start, goal] = &args[..] {
+        let (start, goal) = match (Regex::new(start), Regex::new(goal)) {
+            (Ok(start), Ok(goal)) => (start, goal),
+            (Err(e), _) | (_, Err(e)) => {
+                eprintln!("{}", e);
+                return;
+            }
+        };
+        let (network, instructions) = match compile(input) {
+            Ok(compiled) => compiled,
+            Err(e) => {
+                eprintln!("{}", e);
+                return;
+            }
+        };
+        let (starts, goals) = (network.matching(&start), network.matching(&goal));
+        match command.as_str() {
+            "walk" => match network.walk(&starts, &goals, &instructions) {
+                Ok(steps) => println!("{}", steps),
+                Err(e) => eprintln!("{}", e),
+            },
+            "analyse" => print!(
+                "{}",
+                analysis::analyse(&network, &starts, &goals, &instructions)
+            ),
+            _ => eprintln!("unknown command {:?}", command),
+        }
+        return;
+    }
+    for (part, result) in [("Part 1", part1(input)), ("Part 2", part2(input))] {
+        match result {
+            Ok(steps) => println!("{}: {}", part, steps),
+            Err(e) => println!("{}: {}", part, e),
+        }
+    }
 }
 
 type Instructions = Vec<char>;
 
 fn parse(input: &str) -> IResult<&str, (Instructions, HashMap<&str, Vec<&str>>)> {
     let (input, (instructions, network)) = separated_pair(
-        alpha1,
+        alphanumeric1,
         tag("\n\n"),
         separated_list1(line_ending, parse_line),
     )(input)?;
@@ -32,102 +74,50 @@ fn parse_line(input: &str) -> IResult<&str, (&str, Vec<&str>)> {
         tag(" = "),
         preceded(
             tag("("),
-            terminated(separated_list1(tag(", "), alphanumeric1), tag(")")),
+            terminated(separated_list0(tag(", "), alphanumeric1), tag(")")),
         ),
     )(input)
 }
 
-fn run_instructions(network: HashMap<&str, Vec<&str>>, instructions: Instructions) -> usize {
-    static TARGET_NODE: &str = "ZZZ";
-    let mut cur_node = "AAA";
-    let mut cur_instruction_index = 0;
-    let mut steps = 0;
-    while cur_node != TARGET_NODE {
-        // if we reach the end of the instruction array we wrap around to the start
-        if cur_instruction_index >= instructions.len() {
-            cur_instruction_index = 0;
-        }
-        let cur_instruction = instructions[cur_instruction_index];
-        let left = network.get(cur_node).unwrap()[0];
-        let right = network.get(cur_node).unwrap()[1];
-        match cur_instruction {
-            'L' => {
-                cur_node = left;
-            }
-            'R' => {
-                cur_node = right;
-            }
-            _ => {
-                panic!("Invalid instruction: {}", cur_instruction);
-            }
-        }
-        steps += 1;
-        cur_instruction_index += 1;
-    }
-    steps
+fn compile(input: &str) -> Result<(Network<'_>, Vec<Instruction>), String> {
+    let (_, (instructions, nodes)) = parse(input).map_err(|e| e.to_string())?;
+    Ok((
+        Network::compile(&nodes)?,
+        compile_instructions(&instructions)?,
+    ))
 }
 
-fn run_instructions_endswith(
-    network: &HashMap<&str, Vec<&str>>,
-    start_node: &str,
-    instructions: &Instructions,
-) -> usize {
-    let mut cur_node = start_node;
-    let mut cur_instruction_index = 0;
-    let mut steps = 0;
-    while !cur_node.ends_with("Z") {
-        // if we reach the end of the instruction array we wrap around to the start
-        if cur_instruction_index >= instructions.len() {
-            cur_instruction_index = 0;
-        }
-        let cur_instruction = instructions[cur_instruction_index];
-        let left = network.get(cur_node).unwrap()[0];
-        let right = network.get(cur_node).unwrap()[1];
-        match cur_instruction {
-            'L' => {
-                cur_node = left;
-            }
-            'R' => {
-                cur_node = right;
-            }
-            _ => {
-                panic!("Invalid instruction: {}", cur_instruction);
-            }
-        }
-        steps += 1;
-        cur_instruction_index += 1;
-    }
-    steps
+// Steps until a ghost on every start node is at a goal at the same time
+fn steps(
+    input: &str,
+    is_start: impl Fn(&str) -> bool,
+    is_goal: impl Fn(&str) -> bool,
+) -> Result<u128, String> {
+    let (network, instructions) = compile(input)?;
+    network.walk(
+        &network.select(is_start),
+        &network.select(is_goal),
+        &instructions,
+    )
 }
 
-fn part1(input: &str) -> usize {
-    let (_, (instructions, network)) = parse(input).unwrap();
-    run_instructions(network, instructions)
+fn part1(input: &str) -> Result<u128, String> {
+    steps(input, |node| node == "AAA", |node| node == "ZZZ")
 }
 
-fn part2(input: &str) -> usize {
-    let (_, (instructions, network)) = parse(input).unwrap();
-    let start_nodes = &network
-        .keys()
-        .filter(|k| k.ends_with("A"))
-        .collect::<Vec<_>>();
-
-    let steps = start_nodes
-        .iter()
-        .map(|node| run_instructions_endswith(&network, node, &instructions))
-        .collect::<Vec<usize>>();
-
-    // get the lowest common multiple of all the steps
-    steps
-        .iter()
-        .fold(steps[0], |acc, &x| num::integer::lcm(acc, x))
+fn part2(input: &str) -> Result<u128, String> {
+    steps(
+        input,
+        |node| node.ends_with('A'),
+        |node| node.ends_with('Z'),
+    )
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    static EXAMPLE1: &str = r#"RL
+    pub static EXAMPLE1: &str = r#"RL
 
 AAA = (BBB, CCC)
 BBB = (DDD, EEE)
@@ -143,7 +133,7 @@ AAA = (BBB, BBB)
 BBB = (AAA, ZZZ)
 ZZZ = (ZZZ, ZZZ)"#;
 
-    static EXAMPLE3: &str = r#"LR
+    pub static EXAMPLE3: &str = r#"LR
 
 11A = (11B, XXX)
 11B = (XXX, 11Z)
@@ -162,7 +152,7 @@ XXX = (XXX, XXX)"#;
                 "",
                 (
                     vec!['R', 'L'],
-                    vec![
+                    [
                         ("AAA", vec!["BBB", "CCC"]),
                         ("BBB", vec!["DDD", "EEE"]),
                         ("CCC", vec!["ZZZ", "GGG"]),
@@ -183,7 +173,7 @@ XXX = (XXX, XXX)"#;
                 "",
                 (
                     vec!['L', 'L', 'R'],
-                    vec![
+                    [
                         ("AAA", vec!["BBB", "BBB"]),
                         ("BBB", vec!["AAA", "ZZZ"]),
                         ("ZZZ", vec!["ZZZ", "ZZZ"]),
@@ -198,12 +188,24 @@ XXX = (XXX, XXX)"#;
 
     #[test]
     fn test_part1() {
-        assert_eq!(part1(EXAMPLE1), 2);
-        assert_eq!(part1(EXAMPLE2), 6);
+        assert_eq!(part1(EXAMPLE1), Ok(2));
+        assert_eq!(part1(EXAMPLE2), Ok(6));
     }
 
     #[test]
     fn test_part2() {
-        assert_eq!(part2(EXAMPLE3), 6);
+        assert_eq!(part2(EXAMPLE3), Ok(6));
+    }
+
+    #[test]
+    fn test_goal_then_stuck() {
+        // ZZZ has no second successor, but the walk is already over by then
+        assert_eq!(part1("LR\n\nAAA = (ZZZ, AAA)\nZZZ = (ZZZ)"), Ok(1));
+        assert_eq!(
+            part2(
+                "LR\n\n11A = (11Z, 11A)\n11Z = (11Z)\n22A = (22B, 22A)\n22B = (22Z)\n22Z = (22Z)"
+            ),
+            Err("11Z has no successor 1 (1 successors)".to_string())
+        );
     }
 }
@@ -0,0 +1,228 @@
+use std::collections::HashMap;
+
+use regex::Regex;
+
+use crate::ghost::Cycle;
+
+// The network with every node swapped for its index in `names`, so a step is
+// an array lookup rather than hashing strings. A node can have any number of
+// successors, including none.
+#[derive(Debug, PartialEq)]
+pub struct Network<'a> {
+    names: Vec<&'a str>,
+    next: Vec<Vec<usize>>,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Instruction {
+    // go to the successor with this index
+    Take(usize),
+    // stay on the same node
+    Stay,
+    // go back to the node the last move came from. Staying doesn't count as a
+    // move, and before the first move there's nowhere to go back to, so the
+    // ghost stays where it is.
+    Back,
+}
+
+// A ghost's position: where it is and where its last move came from
+pub type State = (usize, usize);
+
+impl<'a> Network<'a> {
+    pub fn compile(nodes: &HashMap<&'a str, Vec<&'a str>>) -> Result<Network<'a>, String> {
+        let mut names = nodes.keys().copied().collect::<Vec<_>>();
+        names.sort();
+        let ids = names
+            .iter()
+            .enumerate()
+            .map(|(id, &name)| (name, id))
+            .collect::<HashMap<_, _>>();
+        let next = names
+            .iter()
+            .map(|name| {
+                nodes[name]
+                    .iter()
+                    .map(|n| {
+                        ids.get(n)
+                            .copied()
+                            .ok_or(format!("{} -> {} doesn't exist", name, n))
+                    })
+                    .collect::<Result<_, String>>()
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(Network { names, next })
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn name(&self, id: usize) -> &'a str {
+        self.names[id]
+    }
+
+    pub fn successors(&self, id: usize) -> &[usize] {
+        &self.next[id]
+    }
+
+    pub fn step(&self, (node, from): State, instruction: Instruction) -> Result<State, String> {
+        match instruction {
+            Instruction::Take(i) => match self.next[node].get(i) {
+                Some(&next) => Ok((next, node)),
+                None => Err(format!(
+                    "{} has no successor {} ({} successors)",
+                    self.names[node],
+                    i,
+                    self.next[node].len()
+                )),
+            },
+            Instruction::Stay => Ok((node, from)),
+            Instruction::Back => Ok((from, node)),
+        }
+    }
+
+    // the ids of every node whose name passes `predicate`
+    pub fn select(&self, predicate: impl Fn(&str) -> bool) -> Vec<usize> {
+        (0..self.names.len())
+            .filter(|&id| predicate(self.names[id]))
+            .collect()
+    }
+
+    pub fn matching(&self, regex: &Regex) -> Vec<usize> {
+        self.select(|name| regex.is_match(name))
+    }
+
+    // The first step where a ghost on every start node is at one of the
+    // goals at the same time, one ghost being an ordinary walk
+    pub fn walk(
+        &self,
+        starts: &[usize],
+        goals: &[usize],
+        instructions: &[Instruction],
+    ) -> Result<u128, String> {
+        if starts.is_empty() {
+            return Err("no node is a start".to_string());
+        }
+        if goals.is_empty() {
+            return Err("no node is a goal".to_string());
+        }
+        let is_goal = self.goal_mask(goals);
+        let cycles = starts
+            .iter()
+            .map(|&start| self.cycle(start, &is_goal, instructions))
+            .collect::<Vec<_>>();
+        crate::ghost::solve(&cycles)
+    }
+
+    // which nodes are goals, indexed by id
+    pub fn goal_mask(&self, goals: &[usize]) -> Vec<bool> {
+        let mut is_goal = vec![false; self.names.len()];
+        for &goal in goals {
+            is_goal[goal] = true;
+        }
+        is_goal
+    }
+
+    // where a ghost starting on `start` is at a goal, up to the first step
+    // it can't take if there is one
+    pub fn cycle(&self, start: usize, is_goal: &[bool], instructions: &[Instruction]) -> Cycle {
+        Cycle::find(
+            (start, start),
+            instructions.len(),
+            |state, i| self.step(state, instructions[i]),
+            |(node, _)| is_goal[node],
+        )
+    }
+}
+
+// L and R pick the first and second successor, a digit picks the successor
+// with that index, S stays and B goes back
+pub fn compile_instructions(instructions: &[char]) -> Result<Vec<Instruction>, String> {
+    instructions
+        .iter()
+        .map(|c| match c {
+            'L' => Ok(Instruction::Take(0)),
+            'R' => Ok(Instruction::Take(1)),
+            'S' => Ok(Instruction::Stay),
+            'B' => Ok(Instruction::Back),
+            c => c
+                .to_digit(10)
+                .map(|i| Instruction::Take(i as usize))
+                .ok_or(format!("Invalid instruction: {}", c)),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_compile() {
+        let (_, (instructions, nodes)) = parse(
+            "LR
+
+AAA = (BBB, ZZZ)
+BBB = (AAA, AAA)
+ZZZ = (ZZZ, ZZZ)",
+        )
+        .unwrap();
+        let network = Network::compile(&nodes).unwrap();
+        assert_eq!(network.next, vec![[1, 2], [0, 0], [2, 2]]);
+        assert_eq!(network.names[2], "ZZZ");
+        assert_eq!(
+            compile_instructions(&instructions),
+            Ok(vec![Instruction::Take(0), Instruction::Take(1)])
+        );
+        assert!(compile_instructions(&['L', 'X']).is_err());
+
+        let starts = network.matching(&Regex::new("^A").unwrap());
+        let goals = network.select(|n| n == "ZZZ");
+        // L to BBB, R to AAA, L to BBB, R to AAA, ...
+        let [l, r] = [Instruction::Take(0), Instruction::Take(1)];
+        assert!(network.walk(&starts, &goals, &[l, r]).is_err());
+        assert_eq!(network.walk(&starts, &goals, &[r]), Ok(1));
+    }
+
+    #[test]
+    fn test_n_ary() {
+        let (_, (instructions, nodes)) = parse(
+            "2SB12
+
+AAA = (BBB, CCC, DDD)
+BBB = (AAA)
+CCC = (ZZZ, ZZZ, ZZZ)
+DDD = (CCC, AAA, BBB)
+ZZZ = (ZZZ, ZZZ, ZZZ)",
+        )
+        .unwrap();
+        let network = Network::compile(&nodes).unwrap();
+        let instructions = compile_instructions(&instructions).unwrap();
+        assert_eq!(
+            instructions,
+            vec![
+                Instruction::Take(2),
+                Instruction::Stay,
+                Instruction::Back,
+                Instruction::Take(1),
+                Instruction::Take(2)
+            ]
+        );
+        let [aaa, _, _, ddd, zzz] = [0, 1, 2, 3, 4];
+        assert_eq!(network.step((ddd, aaa), Instruction::Back), Ok((aaa, ddd)));
+        // DDD, DDD, back to AAA, CCC, ZZZ
+        assert_eq!(network.walk(&[aaa], &[zzz], &instructions), Ok(5));
+        // from BBB the 2 has nowhere to go
+        assert_eq!(
+            network.walk(&[1], &[zzz], &instructions),
+            Err("BBB has no successor 2 (1 successors)".to_string())
+        );
+    }
+
+    #[test]
+    fn test_missing_node() {
+        let (_, (_, nodes)) = parse("L\n\nAAA = (BBB, AAA)").unwrap();
+        assert!(Network::compile(&nodes).is_err());
+    }
+}
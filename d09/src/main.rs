@@ -1,76 +1,122 @@
-use std::collections::{HashSet, VecDeque};
+mod number;
+mod poly;
+mod table;
 
-fn main() {
-    let input = include_str!("../input.txt");
-    println!("part1: {}", part1(input));
-    println!("part2: {}", part2(input));
-}
+use num::BigInt;
 
-fn predict(line: &str) -> i64 {
-    let mut diffs: Vec<Vec<i64>> = vec![line
-        .split_whitespace()
-        .map(|n| n.parse().unwrap())
-        .collect()];
-    while diffs.last().unwrap().iter().collect::<HashSet<_>>().len() != 1 {
-        diffs.push(diff(&diffs.last().unwrap()));
-    }
-    let ends = diffs.iter().map(|d| d.last().unwrap()).collect::<Vec<_>>();
-    let mut forecasts = vec![diffs.last().unwrap()[0].clone()];
-    for i in 0..ends.len() - 1 {
-        let forecast = forecasts.last().unwrap().clone() + ends[i].clone();
-        forecasts.push(forecast);
-    }
-    forecasts.last().unwrap().clone()
-}
+use number::Number;
+use poly::Polynomial;
+use table::DifferenceTable;
 
-fn history(line: &str) -> i64 {
-    let mut diffs: Vec<Vec<i64>> = vec![line
-        .split_whitespace()
-        .map(|n| n.parse().unwrap())
-        .collect()];
-    while diffs.last().unwrap().iter().collect::<HashSet<_>>().len() != 1 {
-        diffs.push(diff(&diffs.last().unwrap()));
-    }
-    let mut starts = diffs
+fn main() {
+    let input = include_str!("../input.txt");
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    // --big does all the arithmetic with big integers from the start, rather
+    // than only once an i64 would overflow
+    let big = args.iter().any(|arg| arg == "--big");
+    let args = args
         .iter()
-        .rev()
-        .map(|d| d.first().unwrap())
-        .collect::<VecDeque<_>>();
-    starts.pop_front();
-    let mut histories = vec![diffs.last().unwrap()[0].clone()];
-    for i in 0..starts.len() - 1 {
-        let history = starts[i].clone() - histories.last().unwrap().clone();
-        histories.push(history);
+        .filter(|&arg| arg != "--big")
+        .collect::<Vec<_>>();
+    match args.iter().map(|arg| arg.as_str()).collect::<Vec<_>>()[..] {
+        // cargo run -- at <index>, every line's value at that index summed,
+        // 0 being the first value in a line
+        ["at", index] => {
+            let sum = index
+                .parse::<i64>()
+                .map_err(|_| format!("{:?} isn't an index", index))
+                .and_then(|index| extrapolate(input, big, |_| index));
+            match sum {
+                Ok(sum) => println!("{}", sum),
+                Err(e) => println!("{}", e),
+            }
+            return;
+        }
+        // cargo run -- fit <line>, counting lines from 1
+        ["fit", line] => {
+            match fit(input, line, big) {
+                Ok(polynomial) => println!("{}", poly::describe(&polynomial)),
+                Err(e) => println!("{}", e),
+            }
+            return;
+        }
+        // cargo run -- degrees, the degree of every line or why it hasn't one
+        ["degrees"] => {
+            for (i, line) in input.lines().enumerate() {
+                match numbers(line, big).and_then(|values| DifferenceTable::build(&values)) {
+                    Ok(table) => println!("{}: degree {}", i + 1, table.degree()),
+                    Err(e) => println!("{}: {}", i + 1, e),
+                }
+            }
+            return;
+        }
+        _ => {}
+    }
+    for (part, result) in [("part1", part1(input, big)), ("part2", part2(input, big))] {
+        match result {
+            Ok(sum) => println!("{}: {}", part, sum),
+            Err(e) => println!("{}: {}", part, e),
+        }
     }
-    // do one last time
-    histories.push(starts.pop_back().unwrap().clone() - histories.last().unwrap().clone());
-    histories.last().unwrap().clone()
 }
 
-fn diff(numbers: &Vec<i64>) -> Vec<i64> {
-    let mut diffs: Vec<i64> = vec![];
-    numbers.windows(2).for_each(|w| {
-        diffs.push(w[1] - w[0]);
-    });
-    diffs
+// `line` counts from 1
+fn fit(input: &str, line: &str, big: bool) -> Result<Polynomial, String> {
+    let values = line
+        .parse::<usize>()
+        .ok()
+        .and_then(|line| input.lines().nth(line.checked_sub(1)?))
+        .ok_or_else(|| format!("there's no line {:?}", line))?;
+    Polynomial::fit(&numbers(values, big)?)
 }
 
-fn part1(input: &str) -> isize {
+// Each line's polynomial at `index(length of the line)`, summed
+fn extrapolate(input: &str, big: bool, index: impl Fn(usize) -> i64) -> Result<BigInt, String> {
     input
         .lines()
-        .map(|line| predict(line))
-        .collect::<Vec<_>>()
-        .iter()
-        .sum::<i64>() as isize
+        .enumerate()
+        .map(|(i, line)| {
+            let values = numbers(line, big).map_err(|e| format!("line {}: {}", i + 1, e))?;
+            Polynomial::fit(&values)
+                .map(|polynomial| polynomial.at(index(values.len())))
+                .map_err(|e| format!("line {}: {}", i + 1, e))
+        })
+        .sum()
+}
+
+fn numbers(line: &str, big: bool) -> Result<Vec<Number>, String> {
+    line.split_whitespace()
+        .map(|n| Number::parse(n, big))
+        .collect()
+}
+
+fn predict(line: &str, big: bool) -> Result<Number, String> {
+    Ok(DifferenceTable::build(&numbers(line, big)?)?.next())
+}
+
+fn history(line: &str, big: bool) -> Result<Number, String> {
+    Ok(DifferenceTable::build(&numbers(line, big)?)?.previous())
 }
 
-fn part2(input: &str) -> isize {
+// every line's extrapolation summed, or the first line that can't be done
+fn total(
+    input: &str,
+    big: bool,
+    extrapolate: impl Fn(&str, bool) -> Result<Number, String>,
+) -> Result<Number, String> {
     input
         .lines()
-        .map(|line| history(line))
-        .collect::<Vec<_>>()
-        .iter()
-        .sum::<i64>() as isize
+        .enumerate()
+        .map(|(i, line)| extrapolate(line, big).map_err(|e| format!("line {}: {}", i + 1, e)))
+        .sum()
+}
+
+fn part1(input: &str, big: bool) -> Result<Number, String> {
+    total(input, big, predict)
+}
+
+fn part2(input: &str, big: bool) -> Result<Number, String> {
+    total(input, big, history)
 }
 
 #[cfg(test)]
@@ -88,8 +134,8 @@ mod tests {
     #[case("1 3 6 10 15 21", 28)]
     #[case("10 13 16 21 30 45", 68)]
     fn test_predictions(#[case] line: &str, #[case] expected: i64) {
-        let actual = predict(line);
-        assert_eq!(expected, actual);
+        let actual = predict(line, false);
+        assert_eq!(Ok(expected.into()), actual);
     }
 
     #[rstest]
@@ -97,18 +143,82 @@ mod tests {
     #[case("1 3 6 10 15 21", 0)]
     #[case("10 13 16 21 30 45", 5)]
     fn test_history(#[case] line: &str, #[case] expected: i64) {
-        let actual = history(line);
-        assert_eq!(expected, actual);
+        let actual = history(line, false);
+        assert_eq!(Ok(expected.into()), actual);
     }
 
     #[test]
     fn test_part1() {
-        let actual = part1(EXAMPLE);
-        assert_eq!(actual, 114);
+        let actual = part1(EXAMPLE, false);
+        assert_eq!(actual, Ok(114.into()));
     }
     #[test]
     fn test_part2() {
-        let actual = part2(EXAMPLE);
-        assert_eq!(actual, 2);
+        let actual = part2(EXAMPLE, false);
+        assert_eq!(actual, Ok(2.into()));
+    }
+
+    #[test]
+    fn test_not_a_polynomial() {
+        assert_eq!(
+            part1("1 2 3\n1 2 4 8", false),
+            Err(
+                "line 2: 4 values never settle on a constant difference, so aren't a polynomial"
+                    .to_string()
+            )
+        );
+        assert_eq!(part2("", false), Ok(0.into()));
+        assert!(part2("0 1\n\n0 1", false).is_err());
+        assert!(part2("0 1 x", false).is_err());
+    }
+
+    #[test]
+    fn test_big() {
+        // every line fits, but the sum doesn't
+        let near_max = format!("{0} {0} {0}", i64::MAX);
+        let input = [near_max.as_str(), near_max.as_str(), "1 1 1"].join("\n");
+        assert_eq!(
+            part1(&input, false).map(|sum| sum.to_string()),
+            Ok("18446744073709551615".to_string())
+        );
+        // forcing big integers gives the same answers
+        for (small, big) in [
+            (part1(EXAMPLE, false), part1(EXAMPLE, true)),
+            (part2(&input, false), part2(&input, true)),
+        ] {
+            assert!(big.as_ref().is_ok_and(|n| n.is_big()));
+            assert_eq!(small, big);
+        }
+    }
+
+    #[test]
+    fn test_extrapolate() {
+        assert_eq!(
+            extrapolate(EXAMPLE, false, |len| len as i64),
+            Ok(114.into())
+        );
+        assert_eq!(extrapolate(EXAMPLE, true, |_| -1), Ok(2.into()));
+        assert_eq!(extrapolate(EXAMPLE, false, |_| 0), Ok(11.into()));
+        // not a polynomial, for part 1 or here
+        assert!(part1("1 2 4 8", false).is_err());
+        assert!(extrapolate("1 2 4 8", false, |_| 4).is_err());
+        // too big for an i64 from the start
+        let big = "99999999999999999999 100000000000000000000 100000000000000000001";
+        assert_eq!(
+            extrapolate(big, false, |_| 3).map(|sum| sum.to_string()),
+            Ok("100000000000000000002".to_string())
+        );
+        assert_eq!(
+            extrapolate("1 2 x", false, |_| 3),
+            Err("line 1: \"x\" isn't a number".to_string())
+        );
+    }
+
+    #[test]
+    fn test_fit() {
+        assert!(fit(EXAMPLE, "2", false).is_ok_and(|p| p.degree() == Some(2)));
+        for line in ["0", "4", "-1", "two"] {
+            assert!(fit(EXAMPLE, line, false).is_err());
+        }
     }
 }
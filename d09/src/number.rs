@@ -0,0 +1,137 @@
+use std::{
+    fmt,
+    iter::Sum,
+    ops::{Add, Sub},
+};
+
+use num::BigInt;
+
+// An integer that stays an i64 for as long as it can. Every sum and
+// difference is checked, and one that would overflow is done again with
+// arbitrary precision instead, so nothing ever wraps or panics.
+#[derive(Debug, Clone)]
+pub enum Number {
+    Small(i64),
+    Big(BigInt),
+}
+
+impl Number {
+    // `big` makes it a BigInt even if it would fit in an i64
+    pub fn parse(s: &str, big: bool) -> Result<Number, String> {
+        let invalid = |_| format!("{:?} isn't a number", s);
+        match s.parse::<i64>() {
+            Ok(n) if !big => Ok(Number::Small(n)),
+            _ => s.parse::<BigInt>().map(Number::Big).map_err(invalid),
+        }
+    }
+
+    pub fn is_big(&self) -> bool {
+        matches!(self, Number::Big(_))
+    }
+
+    pub fn big(&self) -> BigInt {
+        match self {
+            Number::Small(n) => BigInt::from(*n),
+            Number::Big(n) => n.clone(),
+        }
+    }
+}
+
+impl From<i64> for Number {
+    fn from(n: i64) -> Number {
+        Number::Small(n)
+    }
+}
+
+// by value, however it's stored
+impl PartialEq for Number {
+    fn eq(&self, other: &Number) -> bool {
+        match (self, other) {
+            (Number::Small(a), Number::Small(b)) => a == b,
+            _ => self.big() == other.big(),
+        }
+    }
+}
+
+impl Eq for Number {}
+
+impl Add for &Number {
+    type Output = Number;
+
+    fn add(self, other: &Number) -> Number {
+        match (self, other) {
+            (Number::Small(a), Number::Small(b)) => match a.checked_add(*b) {
+                Some(n) => Number::Small(n),
+                None => Number::Big(BigInt::from(*a) + b),
+            },
+            _ => Number::Big(self.big() + other.big()),
+        }
+    }
+}
+
+impl Sub for &Number {
+    type Output = Number;
+
+    fn sub(self, other: &Number) -> Number {
+        match (self, other) {
+            (Number::Small(a), Number::Small(b)) => match a.checked_sub(*b) {
+                Some(n) => Number::Small(n),
+                None => Number::Big(BigInt::from(*a) - b),
+            },
+            _ => Number::Big(self.big() - other.big()),
+        }
+    }
+}
+
+impl<'a> Sum<&'a Number> for Number {
+    fn sum<I: Iterator<Item = &'a Number>>(iter: I) -> Number {
+        iter.fold(Number::Small(0), |total, n| &total + n)
+    }
+}
+
+impl Sum for Number {
+    fn sum<I: Iterator<Item = Number>>(iter: I) -> Number {
+        iter.fold(Number::Small(0), |total, n| &total + &n)
+    }
+}
+
+impl fmt::Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Number::Small(n) => write!(f, "{}", n),
+            Number::Big(n) => write!(f, "{}", n),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fallback() {
+        let max = Number::from(i64::MAX);
+        let one = Number::from(1);
+        let sum = &max + &one;
+        assert!(sum.is_big());
+        assert_eq!(sum.to_string(), "9223372036854775808");
+        assert_eq!(&sum - &one, max);
+        assert!((&Number::from(i64::MIN) - &one).is_big());
+        assert!(!(&max - &one).is_big());
+        assert_eq!(
+            [max.clone(), max.clone(), Number::from(2)]
+                .iter()
+                .sum::<Number>()
+                .to_string(),
+            "18446744073709551616"
+        );
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Number::parse("-12", false), Ok(Number::Small(-12)));
+        assert!(Number::parse("-12", true).is_ok_and(|n| n.is_big() && n == Number::from(-12)));
+        assert!(Number::parse("99999999999999999999", false).is_ok_and(|n| n.is_big()));
+        assert!(Number::parse("twelve", false).is_err());
+    }
+}
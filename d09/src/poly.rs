@@ -0,0 +1,168 @@
+use std::fmt;
+
+use num::{BigInt, BigRational, One, Zero};
+
+use crate::{number::Number, table::DifferenceTable};
+
+// The polynomial through a sequence, taking the values to be at x = 0, 1, 2,
+// ... It's kept in Newton's forward difference form, straight from the
+// sequence's difference table
+//   p(x) = sum over k of (k-th difference at 0) * C(x, k)
+// which is exact in integers for any integer x, even negative ones.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Polynomial {
+    // the first value in each row of the difference table, with trailing
+    // zeros dropped
+    forward: Vec<BigInt>,
+}
+
+impl Polynomial {
+    // fails for the same sequences as the difference table
+    pub fn fit(values: &[Number]) -> Result<Polynomial, String> {
+        let table = DifferenceTable::build(values)?;
+        let mut forward = table.forward().map(Number::big).collect::<Vec<_>>();
+        while forward.last().is_some_and(|f| f.is_zero()) {
+            forward.pop();
+        }
+        Ok(Polynomial { forward })
+    }
+
+    // None for the zero polynomial
+    pub fn degree(&self) -> Option<usize> {
+        self.forward.len().checked_sub(1)
+    }
+
+    pub fn at(&self, x: i64) -> BigInt {
+        let x = BigInt::from(x);
+        let mut total = BigInt::zero();
+        // C(x, k) = C(x, k - 1) * (x - k + 1) / k, and the division is exact
+        let mut binomial = BigInt::one();
+        for (k, difference) in self.forward.iter().enumerate() {
+            if k > 0 {
+                binomial = binomial * (&x - k + 1) / k;
+            }
+            total += difference * &binomial;
+        }
+        total
+    }
+
+    // Coefficients of 1, x, x^2, ..., which can be fractions even though
+    // every value at an integer is a whole number
+    pub fn coefficients(&self) -> Vec<BigRational> {
+        let mut coefficients = vec![BigRational::zero(); self.forward.len()];
+        // x (x - 1) ... (x - k + 1) / k!
+        let mut basis = vec![BigRational::one()];
+        for (k, difference) in self.forward.iter().enumerate() {
+            if k > 0 {
+                let k = BigRational::from_integer(k.into());
+                let mut next = vec![BigRational::zero(); basis.len() + 1];
+                for (i, b) in basis.iter().enumerate() {
+                    next[i + 1] += b / &k;
+                    next[i] -= b * (&k - BigRational::one()) / &k;
+                }
+                basis = next;
+            }
+            for (c, b) in coefficients.iter_mut().zip(&basis) {
+                *c += b * BigRational::from_integer(difference.clone());
+            }
+        }
+        coefficients
+    }
+}
+
+impl fmt::Display for Polynomial {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let terms = self
+            .coefficients()
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| !c.is_zero())
+            .map(|(power, c)| match power {
+                0 => format!("{}", c),
+                1 => format!("{} x", c),
+                _ => format!("{} x^{}", c, power),
+            })
+            .collect::<Vec<_>>();
+        if terms.is_empty() {
+            return write!(f, "0");
+        }
+        let mut out = terms[0].clone();
+        for term in &terms[1..] {
+            match term.strip_prefix('-') {
+                Some(term) => out += &format!(" - {}", term),
+                None => out += &format!(" + {}", term),
+            }
+        }
+        write!(f, "{}", out)
+    }
+}
+
+// for printing a fitted sequence
+pub fn describe(polynomial: &Polynomial) -> String {
+    match polynomial.degree() {
+        None => "zero".to_string(),
+        Some(degree) => format!("degree {}: {}", degree, polynomial),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    fn ratio(n: i64, d: i64) -> BigRational {
+        BigRational::new(n.into(), d.into())
+    }
+
+    fn fit(values: &[i64]) -> Result<Polynomial, String> {
+        Polynomial::fit(&values.iter().map(|&v| v.into()).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn test_fit() {
+        let p = fit(&[1, 3, 6, 10, 15, 21]).unwrap();
+        assert_eq!(p.degree(), Some(2));
+        // (x + 1) (x + 2) / 2
+        assert_eq!(
+            p.coefficients(),
+            vec![ratio(1, 1), ratio(3, 2), ratio(1, 2)]
+        );
+        assert_eq!(p.to_string(), "1 + 3/2 x + 1/2 x^2");
+        assert_eq!(fit(&[0, 0, 0]).unwrap().degree(), None);
+        assert_eq!(fit(&[0, 0, 0]).unwrap().to_string(), "0");
+        assert_eq!(fit(&[5, 3, 1]).unwrap().to_string(), "5 - 2 x");
+        // the same sequences part 1 and part 2 can't extrapolate
+        assert!(fit(&[]).is_err());
+        assert!(fit(&[1, 2, 4, 8]).is_err());
+    }
+
+    #[rstest]
+    #[case(&[0, 3, 6, 9, 12, 15], 6, 18)]
+    #[case(&[0, 3, 6, 9, 12, 15], -1, -3)]
+    #[case(&[1, 3, 6, 10, 15, 21], 1000, 501501)]
+    #[case(&[1, 3, 6, 10, 15, 21], -50, 1176)]
+    #[case(&[10, 13, 16, 21, 30, 45], 6, 68)]
+    #[case(&[10, 13, 16, 21, 30, 45], -1, 5)]
+    fn test_at(#[case] values: &[i64], #[case] x: i64, #[case] expected: i64) {
+        assert_eq!(fit(values).unwrap().at(x), expected.into());
+    }
+
+    #[test]
+    fn test_coefficients_agree_with_values() {
+        // (x^4 - 3 x^3 + 5 x^2 + x) / 2 + 5
+        let values = [5, 7, 12, 29, 79, 195, 422, 817];
+        let p = fit(&values).unwrap();
+        for x in -20..20 {
+            let x_r = BigRational::from_integer(x.into());
+            let mut power = BigRational::one();
+            let mut total = BigRational::zero();
+            for c in p.coefficients() {
+                total += c * &power;
+                power *= &x_r;
+            }
+            assert_eq!(total, BigRational::from_integer(p.at(x)));
+            assert!(total.is_integer());
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use crate::number::Number;
+
+// The rows of differences down to the first constant one. A row only counts
+// as constant once it has at least two equal values: a lone value is
+// constant whatever comes next, so a table that runs down to one value
+// without settling first says nothing about the sequence beyond its own
+// values and is treated as not being a polynomial at all.
+#[derive(Debug, PartialEq)]
+pub struct DifferenceTable {
+    rows: Vec<Vec<Number>>,
+}
+
+impl DifferenceTable {
+    pub fn build(values: &[Number]) -> Result<DifferenceTable, String> {
+        if values.is_empty() {
+            return Err("there are no values".to_string());
+        }
+        let mut rows = vec![values.to_vec()];
+        loop {
+            let row = rows.last().unwrap();
+            if row.len() < 2 {
+                return Err(format!(
+                    "{} values never settle on a constant difference, so aren't a polynomial",
+                    values.len()
+                ));
+            }
+            if row.iter().all(|v| *v == row[0]) {
+                return Ok(DifferenceTable { rows });
+            }
+            rows.push(row.windows(2).map(|w| &w[1] - &w[0]).collect());
+        }
+    }
+
+    // the number of times the values were differenced before settling
+    pub fn degree(&self) -> usize {
+        self.rows.len() - 1
+    }
+
+    // the first value of every row, Newton's forward differences
+    pub fn forward(&self) -> impl Iterator<Item = &Number> {
+        self.rows.iter().map(|row| row.first().unwrap())
+    }
+
+    // the value after the last one: each row grows by the value below it
+    pub fn next(&self) -> Number {
+        self.rows.iter().map(|row| row.last().unwrap()).sum()
+    }
+
+    // the value before the first one, working back up from the constant row
+    pub fn previous(&self) -> Number {
+        self.rows
+            .iter()
+            .rev()
+            .fold(Number::from(0), |below, row| row.first().unwrap() - &below)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    fn build(values: &[i64]) -> Result<DifferenceTable, String> {
+        DifferenceTable::build(&values.iter().map(|&v| v.into()).collect::<Vec<_>>())
+    }
+
+    #[rstest]
+    #[case(&[0, 3, 6, 9, 12, 15], 1, 18, -3)]
+    #[case(&[1, 3, 6, 10, 15, 21], 2, 28, 0)]
+    #[case(&[10, 13, 16, 21, 30, 45], 3, 68, 5)]
+    #[case(&[7, 7], 0, 7, 7)]
+    #[case(&[0, 0, 0], 0, 0, 0)]
+    #[case(&[1, 4, 9, 16], 2, 25, 0)]
+    fn test_build(
+        #[case] values: &[i64],
+        #[case] degree: usize,
+        #[case] next: i64,
+        #[case] previous: i64,
+    ) {
+        let table = build(values).unwrap();
+        assert_eq!(table.degree(), degree);
+        assert_eq!(table.next(), next.into());
+        assert_eq!(table.previous(), previous.into());
+    }
+
+    #[rstest]
+    #[case(&[])]
+    #[case(&[5])]
+    #[case(&[1, 2])]
+    // powers of two never settle, however many there are
+    #[case(&[1, 2, 4, 8, 16, 32, 64, 128])]
+    fn test_not_a_polynomial(#[case] values: &[i64]) {
+        assert!(build(values).is_err());
+    }
+
+    #[test]
+    fn test_overflow() {
+        // a (x - 1) (x - 2) - a, whose differences of 2a are already out of
+        // range
+        let a = 3 << 61;
+        let table = build(&[a, -a, -a, a]).unwrap();
+        assert_eq!(table.degree(), 2);
+        assert!(table.next().is_big());
+        assert_eq!(table.next().to_string(), "34587645138205409280");
+        assert_eq!(table.previous(), table.next());
+        // the last step only just overflows
+        let table = build(&[i64::MAX - 2, i64::MAX - 1, i64::MAX]).unwrap();
+        assert_eq!(table.next().to_string(), "9223372036854775808");
+        assert_eq!(table.previous(), Number::from(i64::MAX - 3));
+        assert!(!table.previous().is_big());
+    }
+}
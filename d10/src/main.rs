@@ -1,6 +1,11 @@
 fn main() {
     let input = include_str!("../input.txt");
-    println!("Part 1: {}", part1(input));
+    for (part, result) in [("Part 1", part1(input)), ("Part 2", part2(input))] {
+        match result {
+            Ok(answer) => println!("{}: {}", part, answer),
+            Err(e) => println!("{}: {}", part, e),
+        }
+    }
 }
 
 fn parse(input: &str) -> Vec<Vec<char>> {
@@ -36,91 +41,140 @@ impl Direction {
     }
 }
 
-fn next_coordinate(pipe: char, entry_direction: (isize, isize)) -> (isize, isize) {
+fn next_coordinate(pipe: char, entry_direction: (isize, isize)) -> Result<(isize, isize), String> {
     let entry_direction = Direction::from_vector(entry_direction);
-    match pipe {
+    let next = match pipe {
         // (x, y)
         'F' => match entry_direction {
             Direction::Left => Direction::Down, // from the right
             Direction::Up => Direction::Right,  // from the bottom
-            _ => panic!(
-                "Invalid entry direction for {}: {:?}",
-                pipe, entry_direction
-            ),
+            _ => {
+                return Err(format!(
+                    "Invalid entry direction for {}: {:?}",
+                    pipe, entry_direction
+                ))
+            }
         },
         'J' => match entry_direction {
             Direction::Right => Direction::Up,  // from the left
             Direction::Down => Direction::Left, // from the top
-            _ => panic!(
-                "Invalid entry direction for {}: {:?}",
-                pipe, entry_direction
-            ),
+            _ => {
+                return Err(format!(
+                    "Invalid entry direction for {}: {:?}",
+                    pipe, entry_direction
+                ))
+            }
         },
         'L' => match entry_direction {
             Direction::Left => Direction::Up,    // from the right
             Direction::Down => Direction::Right, // from the top
-            _ => panic!(
-                "Invalid entry direction for {}: {:?}",
-                pipe, entry_direction
-            ),
+            _ => {
+                return Err(format!(
+                    "Invalid entry direction for {}: {:?}",
+                    pipe, entry_direction
+                ))
+            }
         },
         '7' => match entry_direction {
             Direction::Right => Direction::Down, // from the left
             Direction::Up => Direction::Left,    // from the bottom
-            _ => panic!(
-                "Invalid entry direction for {}: {:?}",
-                pipe, entry_direction
-            ),
+            _ => {
+                return Err(format!(
+                    "Invalid entry direction for {}: {:?}",
+                    pipe, entry_direction
+                ))
+            }
         },
         '-' => match entry_direction {
             Direction::Left => Direction::Left,   // from the left
             Direction::Right => Direction::Right, // from the bottom
-            _ => panic!(
-                "Invalid entry direction for {}: {:?}",
-                pipe, entry_direction
-            ),
+            _ => {
+                return Err(format!(
+                    "Invalid entry direction for {}: {:?}",
+                    pipe, entry_direction
+                ))
+            }
         },
         '|' => match entry_direction {
             Direction::Up => Direction::Up,     // from the left
             Direction::Down => Direction::Down, // from the bottom
-            _ => panic!(
-                "Invalid entry direction for {}: {:?}",
-                pipe, entry_direction
-            ),
+            _ => {
+                return Err(format!(
+                    "Invalid entry direction for {}: {:?}",
+                    pipe, entry_direction
+                ))
+            }
         },
-        _ => panic!("Invalid pipe: {}", pipe),
-    }
-    .to_vector()
+        _ => return Err(format!("Invalid pipe: {}", pipe)),
+    };
+    Ok(next.to_vector())
 }
 
-fn part1(input: &str) -> usize {
-    let grid = parse(input);
-    let mut x: usize = 0; // current x position
-    let mut y: usize = 0; // current y position
-
-    // find the starting position
-    'outer: for (i, row) in grid.iter().enumerate() {
-        for (j, pipe) in row.iter().enumerate() {
-            if *pipe == 'S' {
-                x = j;
-                y = i;
-                break 'outer;
-            }
-        }
-    }
+// The tiles of the main loop in the order they're walked, starting with 'S'.
+// Pipes that aren't part of it never get visited.
+fn main_loop(grid: &[Vec<char>]) -> Result<Vec<(usize, usize)>, String> {
+    let start = grid
+        .iter()
+        .enumerate()
+        .find_map(|(y, row)| row.iter().position(|&pipe| pipe == 'S').map(|x| (x, y)))
+        .ok_or("there's no S")?;
+
+    // S hides which way its pipe goes, and a neighbour pointing at it can
+    // just as well be junk, so try leaving it every way and keep the first
+    // walk that comes back round
+    [
+        Direction::Right,
+        Direction::Down,
+        Direction::Left,
+        Direction::Up,
+    ]
+    .into_iter()
+    .find_map(|direction| walk(grid, start, direction.to_vector()).ok())
+    .ok_or_else(|| "no loop goes through S".to_string())
+}
 
-    x += 1; // start to the right of 'S'
-    let mut distance = 1;
-    let mut direction = Direction::Right.to_vector();
-    while grid[y][x] != 'S' {
-        direction = next_coordinate(grid[y][x], direction);
+// Follows the pipes from 'S' until they lead back to it, failing if they run
+// into ground, off the grid or into a pipe that doesn't connect
+fn walk(
+    grid: &[Vec<char>],
+    start: (usize, usize),
+    mut direction: (isize, isize),
+) -> Result<Vec<(usize, usize)>, String> {
+    let (mut x, mut y) = start;
+    let mut tiles = vec![start];
+    loop {
         (x, y) = (
-            (x as isize + direction.0) as usize,
-            (y as isize + direction.1) as usize,
+            x.checked_add_signed(direction.0).ok_or("off the grid")?,
+            y.checked_add_signed(direction.1).ok_or("off the grid")?,
         );
-        distance += 1;
+        let pipe = *grid
+            .get(y)
+            .and_then(|row| row.get(x))
+            .ok_or("off the grid")?;
+        if pipe == 'S' {
+            return Ok(tiles);
+        }
+        tiles.push((x, y));
+        direction = next_coordinate(pipe, direction)?;
     }
-    distance / 2
+}
+
+fn part1(input: &str) -> Result<usize, String> {
+    Ok(main_loop(&parse(input))?.len() / 2)
+}
+
+// The loop's tiles are the vertices of a polygon, whose area the shoelace
+// formula gives. Pick's theorem, A = i + b / 2 - 1, then turns that into the
+// number of whole tiles inside, with b the number of tiles on the loop.
+fn part2(input: &str) -> Result<usize, String> {
+    let tiles = main_loop(&parse(input))?;
+    let twice_area = tiles
+        .iter()
+        .zip(tiles.iter().cycle().skip(1))
+        .map(|(&(x1, y1), &(x2, y2))| (x1 * y2) as isize - (x2 * y1) as isize)
+        .sum::<isize>()
+        .unsigned_abs();
+    Ok((twice_area + 2 - tiles.len()) / 2)
 }
 
 #[cfg(test)]
@@ -132,8 +186,66 @@ SJ.L7
 |F--J
 LJ..."#;
 
+    static ENCLOSED: &str = r#"...........
+.S-------7.
+.|F-----7|.
+.||.....||.
+.||.....||.
+.|L-7.F-J|.
+.|..|.|..|.
+.L--J.L--J.
+..........."#;
+
+    // squeezing between pipes doesn't get a tile out of the loop
+    static SQUEEZED: &str = r#"..........
+.S------7.
+.|F----7|.
+.||....||.
+.||....||.
+.|L-7F-J|.
+.|..||..|.
+.L--JL--J.
+.........."#;
+
+    static LARGER: &str = r#".F----7F7F7F7F-7....
+.|F--7||||||||FJ....
+.||.FJ||||||||L7....
+FJL7L7LJLJ||LJ.L-7..
+L--J.L7...LJS7F-7L7.
+....F-J..F7FJ|L7L7L7
+....L7.F7||L7|.L7L7|
+.....|FJLJ|FJ|F7|.LJ
+....FJL-7.||.||||...
+....L---J.LJ.LJLJ..."#;
+
+    // full of junk pipes, and S can't be left to the right
+    static JUNK: &str = r#"FF7FSF7F7F7F7F7F---7
+L|LJ||||||||||||F--J
+FL-7LJLJ||||||LJL-77
+F--JF--7||LJLJ7F7FJ-
+L---JF-JLJ.||-FJLJJ7
+|F|F-JF---7F7-L7L|7|
+|FFJF7L7F-JF7|JL---7
+7-L-JL7||F7|L7F-7F7|
+L.L7LFJ|||||FJL7||LJ
+L7JLJL-JLJLJL--JLJ.L"#;
+
     #[test]
     fn test_part1() {
-        assert_eq!(part1(EXAMPLE), 8);
+        assert_eq!(part1(EXAMPLE), Ok(8));
+    }
+
+    #[test]
+    fn test_part2() {
+        assert_eq!(part2(EXAMPLE), Ok(1));
+        assert_eq!(part2(ENCLOSED), Ok(4));
+        assert_eq!(part2(SQUEEZED), Ok(4));
+        assert_eq!(part2(LARGER), Ok(8));
+        assert_eq!(part2(JUNK), Ok(10));
+        // the junk - points back at S, but leads nowhere
+        assert_eq!(part2("F-7..\n|.|..\nL-S-."), Ok(1));
+        assert_eq!(part1("F-7..\n|.|..\nL-S-."), Ok(4));
+        assert!(part2("..\n.S").is_err());
+        assert!(part2("F7\nLJ").is_err());
     }
 }